@@ -0,0 +1,619 @@
+use crate::parse_options::ParseOptions;
+use crate::tags::compiler::CompilerOptions;
+use na::allocator::Allocator;
+use na::{
+    DefaultAllocator, DimName, Quaternion, Real, Rotation3, UnitQuaternion, Vector3, VectorN, U3,
+    U4, U6,
+};
+use nalgebra as na;
+use roxmltree;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::num::{ParseFloatError, ParseIntError};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseRealVectorError {
+    ParseReal(ParseFloatError),
+    WrongNumberOfElements { expected: usize, actual: usize },
+    CommaSeparated,
+}
+
+impl fmt::Display for ParseRealVectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRealVectorError::ParseReal(error) => write!(f, "invalid real value: {}", error),
+            ParseRealVectorError::WrongNumberOfElements { expected, actual } => write!(
+                f,
+                "expected {} elements, but found {} elements",
+                expected, actual
+            ),
+            ParseRealVectorError::CommaSeparated => {
+                write!(f, "values must be separated by whitespace, not commas")
+            }
+        }
+    }
+}
+
+impl Error for ParseRealVectorError {}
+
+impl From<ParseFloatError> for ParseRealVectorError {
+    fn from(error: ParseFloatError) -> Self {
+        ParseRealVectorError::ParseReal(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseOrientationError {
+    InvalidAttribute {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    ZeroNormQuaternion,
+    ZeroLengthAxis(&'static str),
+    ParallelAxes,
+    /// More than one orientation attribute is given. Holds all of them.
+    MultipleOrientations(Vec<&'static str>),
+}
+
+impl fmt::Display for ParseOrientationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseOrientationError::InvalidAttribute { attribute, error } => {
+                write!(f, "invalid {} attribute: {}", attribute, error)
+            }
+            ParseOrientationError::ZeroNormQuaternion => write!(f, "quat has zero norm"),
+            ParseOrientationError::ZeroLengthAxis(attribute) => {
+                write!(f, "{} contains a zero length axis", attribute)
+            }
+            ParseOrientationError::ParallelAxes => write!(f, "xyaxes contains parallel axes"),
+            ParseOrientationError::MultipleOrientations(attributes) => write!(
+                f,
+                "only one orientation may be given, but found {}",
+                attributes.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for ParseOrientationError {}
+
+/// A boolean attribute is not one of the values MuJoCo accepts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseBoolAttributeError(pub String);
+
+impl fmt::Display for ParseBoolAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "expected one of true, false, 1, 0 or auto, but found '{}'",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseBoolAttributeError {}
+
+/// Error of one of the typed readers of `Attr`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrError {
+    InvalidReal {
+        attribute: &'static str,
+        error: ParseFloatError,
+    },
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidBool {
+        attribute: &'static str,
+        error: ParseBoolAttributeError,
+    },
+    InvalidInteger {
+        attribute: &'static str,
+        error: ParseIntError,
+    },
+}
+
+impl fmt::Display for AttrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AttrError::InvalidReal { attribute, error } => {
+                write!(f, "invalid {} attribute: {}", attribute, error)
+            }
+            AttrError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid {} attribute: {}", attribute, error)
+            }
+            AttrError::InvalidBool { attribute, error } => {
+                write!(f, "invalid {} attribute: {}", attribute, error)
+            }
+            AttrError::InvalidInteger { attribute, error } => {
+                write!(f, "invalid {} attribute: {}", attribute, error)
+            }
+        }
+    }
+}
+
+impl Error for AttrError {}
+
+/// Anything attribute values can be looked up on.
+///
+/// This lets elements whose attributes are partially provided by a
+/// default class be parsed like plain XML nodes.
+pub trait AttributeSource {
+    fn attribute(&self, name: &str) -> Option<&str>;
+
+    fn has_attribute(&self, name: &str) -> bool {
+        self.attribute(name).is_some()
+    }
+}
+
+impl<'a, 'input> AttributeSource for roxmltree::Node<'a, 'input> {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        roxmltree::Node::attribute(self, name)
+    }
+}
+
+pub fn parse_real_attribute<N: Real>(text: &str) -> Result<N, ParseFloatError> {
+    let value: f64 = text.trim().parse()?;
+    Ok(na::convert(value))
+}
+
+/// Parse a boolean the way MuJoCo does.
+///
+/// Attributes like `limited` can also be `auto`, which leaves the
+/// decision to the compiler and is returned as `None`.
+pub fn parse_bool_attribute(text: &str) -> Result<Option<bool>, ParseBoolAttributeError> {
+    match text.trim() {
+        "true" | "1" => Ok(Some(true)),
+        "false" | "0" => Ok(Some(false)),
+        "auto" => Ok(None),
+        _ => Err(ParseBoolAttributeError(text.to_string())),
+    }
+}
+
+/// Parse a whitespace separated list of reals into a fixed size vector.
+pub fn parse_real_vector_attribute<N: Real, D: DimName>(
+    text: &str,
+) -> Result<VectorN<N, D>, ParseRealVectorError>
+where
+    DefaultAllocator: Allocator<N, D>,
+{
+    let values = text
+        .split_whitespace()
+        .map(parse_real_attribute)
+        .collect::<Result<Vec<N>, _>>()?;
+
+    if values.len() != D::dim() {
+        return Err(ParseRealVectorError::WrongNumberOfElements {
+            expected: D::dim(),
+            actual: values.len(),
+        });
+    }
+
+    Ok(VectorN::<N, D>::from_iterator(values))
+}
+
+/// Typed readers of optional attributes.
+///
+/// Every reader returns `Ok(None)` if the attribute is missing and an
+/// error naming the attribute if its value cannot be parsed.
+pub struct Attr;
+
+impl Attr {
+    pub fn real<N: Real>(
+        node: &dyn AttributeSource,
+        attribute: &'static str,
+    ) -> Result<Option<N>, AttrError> {
+        node.attribute(attribute)
+            .map(|value| {
+                parse_real_attribute(value)
+                    .map_err(|error| AttrError::InvalidReal { attribute, error })
+            })
+            .transpose()
+    }
+
+    pub fn vec3<N: Real>(
+        options: &ParseOptions,
+        node: &dyn AttributeSource,
+        attribute: &'static str,
+    ) -> Result<Option<Vector3<N>>, AttrError> {
+        node.attribute(attribute)
+            .map(|value| {
+                parse_real_vector_attribute_with_options::<N, U3>(options, value)
+                    .map_err(|error| AttrError::InvalidRealVector { attribute, error })
+            })
+            .transpose()
+    }
+
+    /// `auto` is treated like a missing attribute.
+    pub fn bool(
+        node: &dyn AttributeSource,
+        attribute: &'static str,
+    ) -> Result<Option<bool>, AttrError> {
+        match node.attribute(attribute) {
+            Some(value) => parse_bool_attribute(value)
+                .map_err(|error| AttrError::InvalidBool { attribute, error }),
+            None => Ok(None),
+        }
+    }
+
+    pub fn int<T: FromStr<Err = ParseIntError>>(
+        node: &dyn AttributeSource,
+        attribute: &'static str,
+    ) -> Result<Option<T>, AttrError> {
+        node.attribute(attribute)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|error| AttrError::InvalidInteger { attribute, error })
+            })
+            .transpose()
+    }
+}
+
+/// Parse a list of reals like `parse_real_vector_attribute`, but accept
+/// commas as separators outside of strict mode. Hand edited models
+/// sometimes use them, even though MuJoCo does not.
+pub fn parse_real_vector_attribute_with_options<N: Real, D: DimName>(
+    options: &ParseOptions,
+    text: &str,
+) -> Result<VectorN<N, D>, ParseRealVectorError>
+where
+    DefaultAllocator: Allocator<N, D>,
+{
+    parse_real_vector_attribute(&commas_as_whitespace(options, text)?)
+}
+
+/// Replace commas separating the values of a list with spaces, so that
+/// lists of any length accept the same separators as
+/// `parse_real_vector_attribute_with_options`.
+pub(crate) fn commas_as_whitespace<'a>(
+    options: &ParseOptions,
+    text: &'a str,
+) -> Result<Cow<'a, str>, ParseRealVectorError> {
+    if !text.contains(',') {
+        return Ok(Cow::Borrowed(text));
+    }
+    if options.strict {
+        return Err(ParseRealVectorError::CommaSeparated);
+    }
+    Ok(Cow::Owned(text.replace(',', " ")))
+}
+
+/// Attributes specifying the orientation of an element.
+pub(crate) const ORIENTATION_ATTRIBUTES: &[&str] =
+    &["quat", "axisangle", "euler", "xyaxes", "zaxis"];
+
+/// Parse the frame orientation of an element.
+///
+/// MuJoCo allows the orientation to be given as a `quat`, `axisangle`,
+/// `euler`, `xyaxes` or `zaxis`. Angles are interpreted using the
+/// compiler's `angle` unit. Only the default `eulerseq="xyz"` is
+/// supported. Like in MuJoCo, at most one of them may be given.
+pub fn parse_orientation_attribute<N: Real>(
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &dyn AttributeSource,
+) -> Result<UnitQuaternion<N>, ParseOrientationError> {
+    let epsilon: N = options.degenerate_epsilon();
+
+    let orientations: Vec<_> = ORIENTATION_ATTRIBUTES
+        .iter()
+        .cloned()
+        .filter(|attribute| node.has_attribute(attribute))
+        .collect();
+    if orientations.len() > 1 {
+        return Err(ParseOrientationError::MultipleOrientations(orientations));
+    }
+
+    if let Some(quat) = node.attribute("quat") {
+        let quat =
+            parse_real_vector_attribute_with_options::<N, U4>(options, quat).map_err(|error| {
+                ParseOrientationError::InvalidAttribute {
+                    attribute: "quat",
+                    error,
+                }
+            })?;
+        let quat = Quaternion::new(quat[0], quat[1], quat[2], quat[3]);
+        if quat.norm() <= epsilon {
+            return Err(ParseOrientationError::ZeroNormQuaternion);
+        }
+        return Ok(UnitQuaternion::from_quaternion(quat));
+    }
+
+    if let Some(axisangle) = node.attribute("axisangle") {
+        let axisangle = parse_real_vector_attribute_with_options::<N, U4>(options, axisangle)
+            .map_err(|error| ParseOrientationError::InvalidAttribute {
+                attribute: "axisangle",
+                error,
+            })?;
+        let axis = na::Unit::try_new(
+            Vector3::new(axisangle[0], axisangle[1], axisangle[2]),
+            epsilon,
+        )
+        .ok_or(ParseOrientationError::ZeroLengthAxis("axisangle"))?;
+        // Only the angle is in the compiler's angle unit. The axis is a
+        // direction, so it is never converted.
+        let angle = compiler.angle_to_radians(axisangle[3]);
+        return Ok(UnitQuaternion::from_axis_angle(&axis, angle));
+    }
+
+    if let Some(euler) = node.attribute("euler") {
+        let euler =
+            parse_real_vector_attribute_with_options::<N, U3>(options, euler).map_err(|error| {
+                ParseOrientationError::InvalidAttribute {
+                    attribute: "euler",
+                    error,
+                }
+            })?;
+        // eulerseq="xyz" rotates about the moving axes, so the
+        // rotations compose left to right.
+        let x_rot = UnitQuaternion::from_axis_angle(
+            &Vector3::x_axis(),
+            compiler.angle_to_radians(euler[0]),
+        );
+        let y_rot = UnitQuaternion::from_axis_angle(
+            &Vector3::y_axis(),
+            compiler.angle_to_radians(euler[1]),
+        );
+        let z_rot = UnitQuaternion::from_axis_angle(
+            &Vector3::z_axis(),
+            compiler.angle_to_radians(euler[2]),
+        );
+        return Ok(x_rot * y_rot * z_rot);
+    }
+
+    if let Some(xyaxes) = node.attribute("xyaxes") {
+        let xyaxes = parse_real_vector_attribute_with_options::<N, U6>(options, xyaxes).map_err(
+            |error| ParseOrientationError::InvalidAttribute {
+                attribute: "xyaxes",
+                error,
+            },
+        )?;
+        let x_axis = na::Unit::try_new(Vector3::new(xyaxes[0], xyaxes[1], xyaxes[2]), epsilon)
+            .ok_or(ParseOrientationError::ZeroLengthAxis("xyaxes"))?
+            .into_inner();
+        // MuJoCo orthogonalizes the y axis against the x axis
+        let y_axis = Vector3::new(xyaxes[3], xyaxes[4], xyaxes[5]);
+        let y_axis = y_axis - x_axis * x_axis.dot(&y_axis);
+        let y_axis = na::Unit::try_new(y_axis, epsilon)
+            .ok_or(ParseOrientationError::ParallelAxes)?
+            .into_inner();
+        let z_axis = x_axis.cross(&y_axis);
+        let rotation = na::Rotation3::from_matrix_unchecked(na::Matrix3::from_columns(&[
+            x_axis, y_axis, z_axis,
+        ]));
+        return Ok(UnitQuaternion::from_rotation_matrix(&rotation));
+    }
+
+    if let Some(zaxis) = node.attribute("zaxis") {
+        let zaxis =
+            parse_real_vector_attribute_with_options::<N, U3>(options, zaxis).map_err(|error| {
+                ParseOrientationError::InvalidAttribute {
+                    attribute: "zaxis",
+                    error,
+                }
+            })?;
+        if zaxis.norm() <= epsilon {
+            return Err(ParseOrientationError::ZeroLengthAxis("zaxis"));
+        }
+        // The minimal rotation is undefined when zaxis points along -Z
+        return Ok(UnitQuaternion::rotation_between(&Vector3::z(), &zaxis)
+            .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::x_axis(), N::pi())));
+    }
+
+    Ok(UnitQuaternion::identity())
+}
+
+/// Parse the frame orientation of an element like
+/// `parse_orientation_attribute`, as a rotation matrix.
+pub fn parse_orientation_matrix<N: Real>(
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &dyn AttributeSource,
+) -> Result<Rotation3<N>, ParseOrientationError> {
+    parse_orientation_attribute(options, compiler, node)
+        .map(|rotation| rotation.to_rotation_matrix())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::compiler::AngleUnit;
+    use approx::assert_relative_eq;
+    use na::U2;
+
+    #[test]
+    fn parse_real_vector() {
+        let vector = parse_real_vector_attribute::<f64, U3>(" 1 2.5\t-3 ").unwrap();
+        assert_eq!(vector, Vector3::new(1.0, 2.5, -3.0));
+    }
+
+    #[test]
+    fn parse_real_vector_with_character_references() {
+        // roxmltree expands character references before the value reaches
+        // the parser, so they separate values like literal whitespace.
+        // CDATA sections are not allowed in attribute values at all.
+        let doc =
+            roxmltree::Document::parse(r#"<geom pos="1&#x20;2.5&#x20;-3" size="1&#32;2&#x9;3"/>"#)
+                .unwrap();
+        let node = doc.root_element();
+
+        let pos = parse_real_vector_attribute::<f64, U3>(node.attribute("pos").unwrap());
+        assert_eq!(pos.unwrap(), Vector3::new(1.0, 2.5, -3.0));
+        let size = parse_real_vector_attribute::<f64, U3>(node.attribute("size").unwrap());
+        assert_eq!(size.unwrap(), Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn parse_real_vector_wrong_number_of_elements() {
+        let error = parse_real_vector_attribute::<f64, U2>("1 2 3").unwrap_err();
+        assert_eq!(
+            error,
+            ParseRealVectorError::WrongNumberOfElements {
+                expected: 2,
+                actual: 3
+            }
+        );
+    }
+
+    #[test]
+    fn parse_comma_separated_vector() {
+        let vector = parse_real_vector_attribute_with_options::<f64, U3>(
+            &ParseOptions::default(),
+            "0,0, -9.81",
+        )
+        .unwrap();
+        assert_eq!(vector, Vector3::new(0.0, 0.0, -9.81));
+
+        let strict = ParseOptions::builder().strict(true).build();
+        assert_eq!(
+            parse_real_vector_attribute_with_options::<f64, U3>(&strict, "0,0,-9.81"),
+            Err(ParseRealVectorError::CommaSeparated)
+        );
+        assert!(parse_real_vector_attribute_with_options::<f64, U3>(&strict, "0 0 -9.81").is_ok());
+    }
+
+    #[test]
+    fn parse_bool() {
+        assert_eq!(parse_bool_attribute("true"), Ok(Some(true)));
+        assert_eq!(parse_bool_attribute("0"), Ok(Some(false)));
+        assert_eq!(parse_bool_attribute("auto"), Ok(None));
+        assert_eq!(
+            parse_bool_attribute("yes"),
+            Err(ParseBoolAttributeError(String::from("yes")))
+        );
+    }
+
+    #[test]
+    fn read_typed_attributes() {
+        let doc = roxmltree::Document::parse(
+            r#"<joint range="0.5" pos="1 2 3" limited="true" condim="4"/>"#,
+        )
+        .unwrap();
+        let node = doc.root_element();
+        assert_eq!(Attr::real::<f64>(&node, "range").unwrap(), Some(0.5));
+        assert_eq!(
+            Attr::vec3::<f64>(&ParseOptions::default(), &node, "pos").unwrap(),
+            Some(Vector3::new(1.0, 2.0, 3.0))
+        );
+        assert_eq!(Attr::bool(&node, "limited").unwrap(), Some(true));
+        assert_eq!(Attr::int::<u32>(&node, "condim").unwrap(), Some(4));
+        assert_eq!(Attr::real::<f64>(&node, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn reject_malformed_typed_attributes() {
+        let doc =
+            roxmltree::Document::parse(r#"<joint range="a" pos="1 2" limited="yes" condim="-1"/>"#)
+                .unwrap();
+        let node = doc.root_element();
+        match Attr::real::<f64>(&node, "range").unwrap_err() {
+            AttrError::InvalidReal { attribute, .. } => assert_eq!(attribute, "range"),
+            error => panic!("unexpected error {:?}", error),
+        }
+        assert_eq!(
+            Attr::vec3::<f64>(&ParseOptions::default(), &node, "pos").unwrap_err(),
+            AttrError::InvalidRealVector {
+                attribute: "pos",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 3,
+                    actual: 2
+                }
+            }
+        );
+        match Attr::bool(&node, "limited").unwrap_err() {
+            AttrError::InvalidBool { attribute, .. } => assert_eq!(attribute, "limited"),
+            error => panic!("unexpected error {:?}", error),
+        }
+        match Attr::int::<u32>(&node, "condim").unwrap_err() {
+            AttrError::InvalidInteger { attribute, .. } => assert_eq!(attribute, "condim"),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parse_euler_orientation_in_degrees() {
+        let doc = roxmltree::Document::parse(r#"<geom euler="0 0 90"/>"#).unwrap();
+        let rotation = parse_orientation_attribute::<f64>(
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap();
+        assert_relative_eq!(rotation * Vector3::x(), Vector3::y(), epsilon = 1e-10);
+    }
+
+    #[test]
+    fn reject_multiple_orientations() {
+        let doc = roxmltree::Document::parse(r#"<geom euler="0 0 90" quat="1 0 0 0"/>"#).unwrap();
+        let error = parse_orientation_attribute::<f64>(
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            ParseOrientationError::MultipleOrientations(vec!["quat", "euler"])
+        );
+        assert_eq!(
+            error.to_string(),
+            "only one orientation may be given, but found quat, euler"
+        );
+    }
+
+    #[test]
+    fn parse_orientation_as_matrix() {
+        let doc = roxmltree::Document::parse(r#"<geom euler="30 0 90"/>"#).unwrap();
+        let node = doc.root_element();
+        let options = ParseOptions::default();
+        let compiler = CompilerOptions::default();
+
+        let rotation = parse_orientation_attribute::<f64>(&options, &compiler, &node).unwrap();
+        let matrix = parse_orientation_matrix::<f64>(&options, &compiler, &node).unwrap();
+        assert_relative_eq!(matrix, rotation.to_rotation_matrix(), epsilon = 1e-10);
+    }
+
+    #[test]
+    fn convert_only_the_axisangle_angle() {
+        let parse = |text: &str, angle: AngleUnit| {
+            let doc = roxmltree::Document::parse(text).unwrap();
+            let compiler = CompilerOptions {
+                angle,
+                ..CompilerOptions::default()
+            };
+            parse_orientation_attribute::<f64>(
+                &ParseOptions::default(),
+                &compiler,
+                &doc.root_element(),
+            )
+            .unwrap()
+        };
+
+        let degrees = parse(r#"<geom axisangle="0 0 1 90"/>"#, AngleUnit::Degree);
+        let radians = parse(r#"<geom axisangle="0 0 1 1.5708"/>"#, AngleUnit::Radian);
+        assert_relative_eq!(degrees, radians, epsilon = 1e-5);
+        assert_relative_eq!(degrees * Vector3::x(), Vector3::y(), epsilon = 1e-10);
+
+        // The length of the axis does not affect the angle
+        let long_axis = parse(r#"<geom axisangle="0 0 2 90"/>"#, AngleUnit::Degree);
+        assert_relative_eq!(long_axis, degrees, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn parse_xyaxes_orientation() {
+        let doc = roxmltree::Document::parse(r#"<geom xyaxes="0 1 0 -1 0 0"/>"#).unwrap();
+        let rotation = parse_orientation_attribute::<f64>(
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap();
+        assert_relative_eq!(rotation * Vector3::z(), Vector3::z(), epsilon = 1e-10);
+        assert_relative_eq!(rotation * Vector3::x(), Vector3::y(), epsilon = 1e-10);
+    }
+}
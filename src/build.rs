@@ -0,0 +1,959 @@
+use crate::mass::{self, MassProperties};
+use crate::mjcf_model::{MJCFModel, WORLDBODY_NAME};
+use crate::tags::body::BodyDesc;
+use crate::tags::geom::GeomUserData;
+use crate::tags::joint::JointType;
+use na::{Isometry3, Real, Vector3};
+use nalgebra as na;
+use ncollide3d::shape::{Compound, ShapeHandle};
+use ncollide3d::world::CollisionGroups;
+use nphysics3d::force_generator::ConstantAcceleration;
+use nphysics3d::joint::{BallConstraint, PrismaticConstraint, RevoluteConstraint};
+use nphysics3d::material::{BasicMaterial, MaterialHandle};
+use nphysics3d::object::{
+    BodyHandle, BodyPartHandle, BodyStatus, ColliderDesc, ColliderHandle, RigidBodyDesc,
+};
+use nphysics3d::world::World;
+use std::collections::{HashMap, HashSet};
+
+/// Settings controlling how a model is added to a world.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildOptions {
+    /// Worldbody geom to use as the ground. Every other static collider
+    /// is attached to a static body at the position of this geom
+    /// instead of to the nphysics ground.
+    pub ground_geom: Option<String>,
+    /// Geoms in these groups are left out, e.g. to hide visual geoms.
+    pub hidden_groups: HashSet<i32>,
+}
+
+/// Handles of the nphysics objects created for a model.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildResult {
+    /// Collider of each geom. Geoms that were combined into a compound
+    /// collider all map to the handle of the compound, and geoms in
+    /// hidden groups are left out.
+    pub collider_handles: HashMap<String, ColliderHandle>,
+    /// Body each model body is part of. Bodies without joints are welded
+    /// to an ancestor, so they map to the handle of that ancestor's body
+    /// or of the ground.
+    pub body_handles: HashMap<String, BodyHandle>,
+}
+
+impl<N: Real> MJCFModel<N> {
+    /// Add the model's bodies and colliders to `world` and apply the
+    /// model options.
+    ///
+    /// Every body with a joint becomes a rigid body attached to its
+    /// parent with the matching nphysics constraint. A body without
+    /// joints is welded to its parent just like in MuJoCo, so its
+    /// colliders are attached to the closest ancestor that is a rigid
+    /// body, or to the ground.
+    ///
+    /// A body with several geoms is physically a single rigid shape, so
+    /// its geoms are combined into one compound collider. Geoms of the
+    /// world body are added individually since planes cannot be part of a
+    /// compound shape.
+    ///
+    /// Each rigid body gets the mass properties of its body and of the
+    /// bodies welded to it, computed like `body_mass_properties`, so the
+    /// compiler's `boundmass` and `boundinertia` apply. The colliders
+    /// therefore add no mass of their own.
+    ///
+    /// The `gravcomp` of a body with joints is applied as an acceleration
+    /// opposing gravity, unless the `gravity` flag is disabled. Bodies
+    /// without joints are part of their ancestor's rigid body, so their
+    /// `gravcomp` is ignored.
+    ///
+    /// The world uses the model timestep. nphysics only has a
+    /// semi-implicit Euler integrator, so the model integrator is ignored.
+    ///
+    /// Disabling the `contact` flag puts every collider into collision
+    /// groups that do not interact with any group, and disabling the
+    /// `gravity` flag sets the world gravity to zero.
+    ///
+    /// The sliding friction of each geom becomes the friction of its
+    /// collider's material. nphysics has no torsional or rolling
+    /// friction, so the other friction components are ignored. nphysics
+    /// mixes the materials of a contact itself, so geom priorities only
+    /// apply when every contact a collider can take part in has the same
+    /// friction in MuJoCo. Its material then gets that friction.
+    ///
+    /// nphysics 0.10 has no continuous collision detection, so the `ccd`
+    /// flag of geoms is not applied yet.
+    pub fn build(&self, world: &mut World<N>) -> BuildResult {
+        self.build_with_options(world, &BuildOptions::default())
+    }
+
+    /// Add the model to `world` like `build`, with non-default build
+    /// options.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `options.ground_geom` is not a geom of the world body.
+    pub fn build_with_options(&self, world: &mut World<N>, options: &BuildOptions) -> BuildResult {
+        if self.options().flags().gravity {
+            world.set_gravity(self.options().gravity);
+        } else {
+            world.set_gravity(Vector3::zeros());
+        }
+        world.set_timestep(self.options().timestep);
+
+        // The body part each body is attached to and the world position
+        // of that part
+        let mut parts: HashMap<&str, (BodyPartHandle, Isometry3<N>)> = HashMap::new();
+        let mut result = BuildResult::default();
+
+        for body in self.iter_bodies() {
+            let body_position = self
+                .body_world_position(&body.name)
+                .expect("Body ancestors must be in the model");
+
+            let (parent_part, parent_position) = match &body.parent {
+                Some(parent) => parts[parent.as_str()],
+                None => self.build_ground(world, options),
+            };
+            let (part, part_position) = match body.joints.first() {
+                Some(joint_name) => {
+                    let mass_properties = self.rigid_body_mass_properties(body);
+                    let part = RigidBodyDesc::new()
+                        .position(body_position)
+                        .mass(mass_properties.mass)
+                        .angular_inertia(mass_properties.inertia)
+                        .local_center_of_mass(mass_properties.center_of_mass)
+                        .build(world)
+                        .part_handle();
+                    self.build_joint(
+                        world,
+                        joint_name,
+                        (parent_part, parent_position),
+                        (part, body_position),
+                    );
+                    // There is nothing to compensate without gravity
+                    if body.gravcomp != N::zero() && self.options().flags().gravity {
+                        let mut compensation = ConstantAcceleration::new(
+                            -self.options().gravity * body.gravcomp,
+                            Vector3::zeros(),
+                        );
+                        compensation.add_body_part(part);
+                        world.add_force_generator(compensation);
+                    }
+                    (part, body_position)
+                }
+                None => (parent_part, parent_position),
+            };
+            parts.insert(&body.name, (part, part_position));
+            result
+                .body_handles
+                .insert(body.name.clone(), part.body_handle);
+
+            self.build_body_colliders(
+                world,
+                options,
+                body,
+                part,
+                part_position.inverse() * body_position,
+                &mut result,
+            );
+        }
+
+        result
+    }
+
+    /// Mass properties of the rigid body built for `body`, in the body
+    /// frame. Descendants without joints are welded to the rigid body, so
+    /// their mass is included.
+    fn rigid_body_mass_properties(&self, body: &BodyDesc<N>) -> MassProperties<N> {
+        let body_position = self
+            .body_world_position(&body.name)
+            .expect("Body ancestors must be in the model");
+
+        let mut parts = Vec::new();
+        let mut welded = vec![body];
+        while let Some(welded_body) = welded.pop() {
+            let position = body_position.inverse()
+                * self
+                    .body_world_position(&welded_body.name)
+                    .expect("Body ancestors must be in the model");
+            let properties = self
+                .body_mass_properties(&welded_body.name)
+                .expect("Body must be in the model");
+            parts.push(properties.transformed(&position));
+            welded.extend(
+                welded_body
+                    .children
+                    .iter()
+                    .map(|child| &self.bodies()[child])
+                    .filter(|child| child.joints.is_empty()),
+            );
+        }
+        mass::combine(&parts)
+    }
+
+    /// The body part static colliders are attached to and its world
+    /// position.
+    fn build_ground(
+        &self,
+        world: &mut World<N>,
+        options: &BuildOptions,
+    ) -> (BodyPartHandle, Isometry3<N>) {
+        match &options.ground_geom {
+            Some(geom_name) => {
+                assert_eq!(
+                    self.geom_body(geom_name),
+                    Some(WORLDBODY_NAME),
+                    "Ground geom must be a geom of the world body"
+                );
+                let position = self
+                    .geom_world_position(geom_name)
+                    .expect("Geom body must be in the model");
+                let part = RigidBodyDesc::new()
+                    .position(position)
+                    .status(BodyStatus::Static)
+                    .build(world)
+                    .part_handle();
+                (part, position)
+            }
+            None => (BodyPartHandle::ground(), Isometry3::identity()),
+        }
+    }
+
+    /// Attach the body's colliders to `part` and record their handles in
+    /// `result`. `position` is the body frame relative to the frame of the
+    /// part.
+    fn build_body_colliders(
+        &self,
+        world: &mut World<N>,
+        options: &BuildOptions,
+        body: &BodyDesc<N>,
+        part: BodyPartHandle,
+        position: Isometry3<N>,
+        result: &mut BuildResult,
+    ) {
+        // TODO(dschwab): Enable CCD on colliders of geoms with the ccd
+        // flag once nphysics supports it.
+        // The mass of the rigid body is set when it is built
+        let finish_collider = |collider: ColliderDesc<N>| {
+            let collider = collider.density(N::zero());
+            if self.options().flags().contact {
+                collider
+            } else {
+                collider.collision_groups(CollisionGroups::new().with_whitelist(&[]))
+            }
+        };
+
+        let geoms: Vec<_> = body
+            .geoms
+            .iter()
+            .filter(|geom_name| {
+                GeomUserData::from_collider_desc(&self.colliders()[*geom_name])
+                    .map_or(true, |user_data| {
+                        !options.hidden_groups.contains(&user_data.group)
+                    })
+            })
+            .cloned()
+            .collect();
+
+        if body.name == WORLDBODY_NAME || geoms.len() == 1 {
+            for geom_name in &geoms {
+                let collider = &self.colliders()[geom_name];
+                let handle = finish_collider(
+                    collider
+                        .clone()
+                        .position(position * collider.get_position())
+                        .material(MaterialHandle::new(
+                            self.built_material(std::slice::from_ref(geom_name), options)
+                                .expect("Geom must be in the model"),
+                        )),
+                )
+                .build_with_parent(part, world)
+                .map(|collider| collider.handle());
+                if let Some(handle) = handle {
+                    result.collider_handles.insert(geom_name.clone(), handle);
+                }
+            }
+        } else if let Some(collider) = self.compound_collider(&geoms, position, options) {
+            let handle = finish_collider(collider.name(body.name.clone()))
+                .build_with_parent(part, world)
+                .map(|collider| collider.handle());
+            if let Some(handle) = handle {
+                for geom_name in geoms {
+                    result.collider_handles.insert(geom_name, handle);
+                }
+            }
+        }
+    }
+
+    /// Constrain `child` to `parent` according to the joint. Both parts
+    /// are given with their world position.
+    fn build_joint(
+        &self,
+        world: &mut World<N>,
+        joint_name: &str,
+        parent: (BodyPartHandle, Isometry3<N>),
+        child: (BodyPartHandle, Isometry3<N>),
+    ) {
+        let joint = &self.joints()[joint_name];
+        let (parent_part, parent_position) = parent;
+        let (child_part, child_position) = child;
+
+        // The joint is given in the child body frame, but the parent
+        // constraint frame is the frame of the parent part
+        let child_to_parent = parent_position.inverse() * child_position;
+        let parent_anchor = child_to_parent * joint.pos;
+        let parent_axis = child_to_parent * joint.axis;
+
+        // TODO(dschwab): nphysics constraints cannot be limited, so the
+        // joint range is ignored
+        match joint.joint_type {
+            JointType::Hinge => {
+                world.add_constraint(RevoluteConstraint::new(
+                    parent_part,
+                    child_part,
+                    parent_anchor,
+                    parent_axis,
+                    joint.pos,
+                    joint.axis,
+                ));
+            }
+            JointType::Slide => {
+                world.add_constraint(PrismaticConstraint::new(
+                    parent_part,
+                    child_part,
+                    parent_anchor,
+                    parent_axis,
+                    joint.pos,
+                ));
+            }
+            JointType::Ball => {
+                world.add_constraint(BallConstraint::new(
+                    parent_part,
+                    child_part,
+                    parent_anchor,
+                    joint.pos,
+                ));
+            }
+            // A free body is not constrained at all
+            JointType::Free => {}
+        }
+    }
+
+    /// Build the model into a new world, step it `steps` times at the
+    /// model timestep and return the final world position of each geom.
+    ///
+    /// Meant for headless tests of models. Geoms in hidden groups are not
+    /// built, so they are left out.
+    pub fn simulate(&self, steps: usize) -> HashMap<String, Isometry3<N>> {
+        let mut world = World::new();
+        let result = self.build(&mut world);
+        for _ in 0..steps {
+            world.step();
+        }
+
+        result
+            .collider_handles
+            .iter()
+            .filter_map(|(geom_name, &handle)| {
+                let collider = world.collider(handle)?;
+                // Geoms combined into a compound are positioned relative
+                // to the compound collider
+                let position = if collider.shape().is_shape::<Compound<N>>() {
+                    collider.position() * self.colliders()[geom_name].get_position()
+                } else {
+                    *collider.position()
+                };
+                Some((geom_name.clone(), position))
+            })
+            .collect()
+    }
+
+    /// Material with the sliding friction of the geom and no restitution.
+    ///
+    /// Contacts of geoms with `condim` 1 are frictionless in MuJoCo, so
+    /// their material has no friction.
+    pub fn geom_material(&self, geom_name: &str) -> Option<BasicMaterial<N>> {
+        let collider = self.get_collider(geom_name)?;
+        let friction = GeomUserData::from_collider_desc(collider)
+            .map(|user_data| match user_data.condim {
+                1 => N::zero(),
+                _ => user_data.friction.x,
+            })
+            .unwrap_or_else(N::one);
+        Some(BasicMaterial::new(N::zero(), friction))
+    }
+
+    /// Material of the collider built for the geoms.
+    ///
+    /// nphysics mixes the materials of both colliders of a contact on its
+    /// own, so the friction of each pair cannot be resolved by priority
+    /// like in MuJoCo. Instead, when every contact the geoms can take
+    /// part in has the same friction in MuJoCo, e.g. because they only
+    /// touch a geom of higher priority, the material gets that friction.
+    /// Otherwise it is the material of the first geom.
+    fn built_material(
+        &self,
+        geom_names: &[String],
+        options: &BuildOptions,
+    ) -> Option<BasicMaterial<N>> {
+        let material = self.geom_material(geom_names.first()?)?;
+
+        let mut resolved_friction = None;
+        for geom_name in geom_names {
+            let user_data = match GeomUserData::from_collider_desc(&self.colliders()[geom_name]) {
+                Some(user_data) => user_data,
+                None => return Some(material),
+            };
+            let geom_body = self.geom_body(geom_name);
+            for (partner_name, partner) in self.colliders() {
+                let partner_data = match GeomUserData::from_collider_desc(partner) {
+                    Some(partner_data) => partner_data,
+                    None => return Some(material),
+                };
+                // Geoms of the same body never touch, and hidden geoms
+                // are not built
+                if geom_names.contains(partner_name)
+                    || self.geom_body(partner_name) == geom_body
+                    || options.hidden_groups.contains(&partner_data.group)
+                    || !can_collide(user_data, partner_data)
+                {
+                    continue;
+                }
+                let friction = contact_sliding_friction(user_data, partner_data);
+                match resolved_friction {
+                    None => resolved_friction = Some(friction),
+                    Some(resolved) if resolved == friction => {}
+                    Some(_) => return Some(material),
+                }
+            }
+        }
+
+        Some(match resolved_friction {
+            Some(friction) => BasicMaterial::new(N::zero(), friction),
+            None => material,
+        })
+    }
+
+    /// Combine the colliders of several geoms into a single collider.
+    ///
+    /// The compound gets a single material like `built_material`, since
+    /// nphysics only supports a single material per collider. Its density
+    /// is not used, since the rigid body gets the mass of every geom when
+    /// it is built.
+    fn compound_collider(
+        &self,
+        geom_names: &[String],
+        position: Isometry3<N>,
+        options: &BuildOptions,
+    ) -> Option<ColliderDesc<N>> {
+        if geom_names.is_empty() {
+            return None;
+        }
+        let shapes = geom_names
+            .iter()
+            .map(|geom_name| {
+                let collider = &self.colliders()[geom_name];
+                (*collider.get_position(), collider.get_shape().clone())
+            })
+            .collect();
+
+        Some(
+            ColliderDesc::new(ShapeHandle::new(Compound::new(shapes)))
+                .position(position)
+                .density(N::zero())
+                .material(MaterialHandle::new(
+                    self.built_material(geom_names, options)?,
+                )),
+        )
+    }
+}
+
+/// Whether MuJoCo checks the pair of geoms for contacts, because the
+/// contype of one shares a bit with the conaffinity of the other.
+fn can_collide<N: Real>(geom1: &GeomUserData<N>, geom2: &GeomUserData<N>) -> bool {
+    geom1.contype & geom2.conaffinity != 0 || geom2.contype & geom1.conaffinity != 0
+}
+
+/// Sliding friction MuJoCo uses for a contact between the geoms.
+///
+/// Like the friction, the condim of the geom with the higher priority is
+/// used, or the larger one for equal priorities. Contacts with condim 1
+/// are frictionless.
+fn contact_sliding_friction<N: Real>(geom1: &GeomUserData<N>, geom2: &GeomUserData<N>) -> N {
+    let condim = if geom1.priority > geom2.priority {
+        geom1.condim
+    } else if geom1.priority < geom2.priority {
+        geom2.condim
+    } else {
+        geom1.condim.max(geom2.condim)
+    };
+    match condim {
+        1 => N::zero(),
+        _ => geom1.mixed_friction(geom2).x,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use na::Point3;
+
+    #[test]
+    fn build_multi_geom_body_as_compound() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <body name="dumbbell" pos="0 0 1">
+                     <geom size="0.1" pos="-0.5 0 0"/>
+                     <geom size="0.1" pos="0.5 0 0"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        model.build(&mut world);
+
+        assert_eq!(world.colliders().count(), 2);
+        let compounds: Vec<_> = world
+            .colliders()
+            .filter_map(|collider| collider.shape().as_shape::<Compound<f64>>())
+            .collect();
+        assert_eq!(compounds.len(), 1);
+        assert_eq!(compounds[0].shapes().len(), 2);
+    }
+
+    #[test]
+    fn compound_mass_of_every_geom() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="hammer">
+                     <joint type="free"/>
+                     <geom type="box" size="0.5 0.5 0.5" pos="0 0 -1" density="1"/>
+                     <geom type="box" size="0.5 0.5 0.5" pos="0 0 1" density="3"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        let result = model.build(&mut world);
+
+        let hammer = world.rigid_body(result.body_handles["hammer"]).unwrap();
+        // The geoms have the same volume, so using the density of the
+        // first geom for both would give a mass of 2
+        assert_relative_eq!(hammer.local_inertia().linear, 4.0);
+    }
+
+    #[test]
+    fn return_collider_handles() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <body name="ball" pos="0 0 1">
+                     <joint type="free"/>
+                     <geom name="ball_geom" size="0.1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        let result = model.build(&mut world);
+
+        assert_eq!(result.collider_handles.len(), 2);
+        let floor = result.collider_handles["floor"];
+        let ball = result.collider_handles["ball_geom"];
+        assert_ne!(floor, ball);
+        assert!(world.collider(floor).is_some());
+        assert_eq!(
+            world.collider(ball).unwrap().body(),
+            result.body_handles["ball"]
+        );
+        assert_ne!(
+            result.body_handles["ball"],
+            result.body_handles[WORLDBODY_NAME]
+        );
+    }
+
+    #[test]
+    fn simulate_falling_sphere() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="ball" pos="0 0 1">
+                     <joint type="free"/>
+                     <geom name="ball_geom" size="0.1"/>
+                   </body>
+                   <body name="dumbbell" pos="2 0 1">
+                     <joint type="free"/>
+                     <geom name="left" size="0.1" pos="-0.5 0 0"/>
+                     <geom name="right" size="0.1" pos="0.5 0 0"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let positions = model.simulate(50);
+        assert_eq!(positions.len(), 3);
+        let ball = positions["ball_geom"].translation.vector;
+        assert!(ball.z < 1.0);
+        assert_relative_eq!(ball.x, 0.0, epsilon = 1e-10);
+
+        // Geoms of a compound keep their offsets within the body
+        let left = positions["left"].translation.vector;
+        let right = positions["right"].translation.vector;
+        assert_relative_eq!(right - left, Vector3::new(1.0, 0.0, 0.0), epsilon = 1e-10);
+        assert!(left.z < 1.0);
+    }
+
+    #[test]
+    fn build_hinge_with_offset_anchor() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="pendulum" pos="0 0 2">
+                     <joint name="swing" type="hinge" pos="0 0 0.5" axis="0 1 0"/>
+                     <geom type="capsule" fromto="0 0 0 0 0 0.5" size="0.05"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let joint = model.get_joint("swing").unwrap();
+        assert_eq!(joint.pos, Point3::new(0.0, 0.0, 0.5));
+        assert_eq!(
+            model.joint_world_anchor("swing").unwrap(),
+            Point3::new(0.0, 0.0, 2.5)
+        );
+
+        let mut world = World::new();
+        model.build(&mut world);
+
+        // The pendulum collider is attached to its own rigid body
+        assert_eq!(
+            world
+                .colliders()
+                .filter(|collider| !collider.body().is_ground())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn build_clamped_mass() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <compiler boundmass="5" boundinertia="0.5"/>
+                 <worldbody>
+                   <body name="pebble">
+                     <joint type="free"/>
+                     <geom size="0.01"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        let result = model.build(&mut world);
+
+        let pebble = world.rigid_body(result.body_handles["pebble"]).unwrap();
+        assert_relative_eq!(pebble.local_inertia().linear, 5.0);
+        assert_relative_eq!(
+            pebble.local_inertia().angular,
+            na::Matrix3::identity() * 0.5
+        );
+    }
+
+    #[test]
+    fn gravcomp_cancels_gravity() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="floating" pos="0 0 1" gravcomp="1">
+                     <joint type="free"/>
+                     <geom name="floating_ball" size="0.1"/>
+                   </body>
+                   <body name="falling" pos="1 0 1">
+                     <joint type="free"/>
+                     <geom name="falling_ball" size="0.1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        model.build(&mut world);
+        for _ in 0..10 {
+            world.step();
+        }
+
+        let height = |name: &str| {
+            world
+                .colliders()
+                .find(|collider| collider.name() == name)
+                .unwrap()
+                .position()
+                .translation
+                .vector
+                .z
+        };
+        assert_relative_eq!(height("floating_ball"), 1.0, epsilon = 1.0e-6);
+        assert!(height("falling_ball") < 1.0);
+    }
+
+    #[test]
+    fn build_relative_to_ground_geom() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1" pos="0 0 -1"/>
+                   <geom name="ball" size="0.1" pos="1 0 0"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let options = BuildOptions {
+            ground_geom: Some(String::from("floor")),
+            ..BuildOptions::default()
+        };
+        let mut world = World::new();
+        model.build_with_options(&mut world, &options);
+
+        let floor = world
+            .colliders()
+            .find(|collider| collider.name() == "floor")
+            .unwrap();
+        let ball = world
+            .colliders()
+            .find(|collider| collider.name() == "ball")
+            .unwrap();
+        assert!(!floor.body().is_ground());
+        assert_eq!(ball.body(), floor.body());
+        assert_relative_eq!(
+            ball.position_wrt_body().translation.vector,
+            Vector3::new(1.0, 0.0, 1.0)
+        );
+        assert_relative_eq!(
+            ball.position().translation.vector,
+            Vector3::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn map_sliding_friction_to_material() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="rubber" size="0.1" friction="2 0.1 0.01"/>
+                   <geom name="default" size="0.1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let rubber = model.geom_material("rubber").unwrap();
+        assert_eq!(rubber.friction, 2.0);
+        assert_eq!(rubber.restitution, 0.0);
+        assert_eq!(model.geom_material("default").unwrap().friction, 1.0);
+        assert!(model.geom_material("missing").is_none());
+
+        let mut world = World::new();
+        model.build(&mut world);
+        assert_eq!(world.colliders().count(), 2);
+    }
+
+    #[test]
+    fn inherit_frictionless_condim_from_default() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <default>
+                   <geom condim="1"/>
+                 </default>
+                 <worldbody>
+                   <geom name="ice" size="0.1" friction="2 0.1 0.01"/>
+                   <geom name="rubber" size="0.1" condim="3" friction="2 0.1 0.01"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let ice = model.get_collider("ice").unwrap();
+        assert_eq!(GeomUserData::from_collider_desc(ice).unwrap().condim, 1);
+        assert_eq!(model.geom_material("ice").unwrap().friction, 0.0);
+        assert_eq!(model.geom_material("rubber").unwrap().friction, 2.0);
+
+        let mut world = World::new();
+        let result = model.build(&mut world);
+        assert_eq!(result.collider_handles.len(), 2);
+    }
+
+    #[test]
+    fn slide_on_floor_of_higher_priority() {
+        // Gravity pushes the crate sideways with a tenth of the force
+        // pressing it onto the floor, so it only slides on the floor's
+        // friction
+        let model = |priority: i32| {
+            MJCFModel::<f64>::parse_xml_string(&format!(
+                r#"<mujoco>
+                     <option gravity="1 0 -10"/>
+                     <worldbody>
+                       <geom name="floor" type="plane" size="5 5 0.1" friction="0.05"
+                             priority="{}"/>
+                       <body name="crate" pos="0 0 0.1">
+                         <joint type="free"/>
+                         <geom name="crate_geom" type="box" size="0.1 0.1 0.1" friction="1"/>
+                       </body>
+                     </worldbody>
+                   </mujoco>"#,
+                priority
+            ))
+            .unwrap()
+        };
+
+        let icy = model(1);
+        let options = BuildOptions::default();
+        let crate_geom = [String::from("crate_geom")];
+        assert_eq!(
+            icy.built_material(&crate_geom, &options).unwrap().friction,
+            0.05
+        );
+        let crate_x = icy.simulate(500)["crate_geom"].translation.vector.x;
+        assert!(crate_x > 0.1, "crate only moved to x = {}", crate_x);
+
+        let grippy = model(0);
+        assert_eq!(
+            grippy
+                .built_material(&crate_geom, &options)
+                .unwrap()
+                .friction,
+            1.0
+        );
+        let crate_x = grippy.simulate(500)["crate_geom"].translation.vector.x;
+        assert!(crate_x < 0.01, "crate slid to x = {}", crate_x);
+    }
+
+    #[test]
+    fn skip_hidden_groups() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <geom name="marker" size="0.1" group="3"/>
+                   <body name="arm">
+                     <geom name="collision" size="0.1"/>
+                     <geom name="visual" size="0.1" group="4"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let options = BuildOptions {
+            hidden_groups: [3, 4].iter().cloned().collect(),
+            ..BuildOptions::default()
+        };
+        let mut world = World::new();
+        model.build_with_options(&mut world, &options);
+
+        let mut names: Vec<_> = world
+            .colliders()
+            .map(|collider| collider.name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["collision", "floor"]);
+    }
+
+    #[test]
+    fn build_applies_gravity() {
+        let model =
+            MJCFModel::<f64>::parse_xml_string(r#"<mujoco><option gravity="0 0 -1"/></mujoco>"#)
+                .unwrap();
+
+        let mut world = World::new();
+        model.build(&mut world);
+
+        assert_eq!(world.gravity(), &Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn build_applies_timestep() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco><option timestep="0.01" integrator="RK4"/></mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        model.build(&mut world);
+
+        assert_eq!(world.timestep(), 0.01);
+    }
+
+    #[test]
+    fn gravcomp_without_gravity() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <option><flag gravity="disable"/></option>
+                 <worldbody>
+                   <body name="floating" pos="0 0 1" gravcomp="1">
+                     <joint type="free"/>
+                     <geom name="floating_ball" size="0.1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        model.build(&mut world);
+        for _ in 0..10 {
+            world.step();
+        }
+
+        let ball = world
+            .colliders()
+            .find(|collider| collider.name() == "floating_ball")
+            .unwrap();
+        assert_relative_eq!(
+            ball.position().translation.vector,
+            Vector3::new(0.0, 0.0, 1.0),
+            epsilon = 1.0e-9
+        );
+    }
+
+    #[test]
+    fn build_applies_flags() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <option><flag contact="disable" gravity="disable"/></option>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        model.build(&mut world);
+
+        assert_eq!(world.gravity(), &Vector3::zeros());
+        let floor = world
+            .colliders()
+            .find(|collider| collider.name() == "floor")
+            .unwrap();
+        assert!(!floor
+            .collision_groups()
+            .can_interact_with_groups(&CollisionGroups::new()));
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Category of a problem that was recovered from while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+    /// A geom could not be parsed and was left out of the model.
+    SkippedGeom,
+    /// An element the parser does not know was ignored.
+    UnsupportedElement,
+    /// An attribute of a geom, body, joint or site the parser does not
+    /// know was ignored.
+    UnsupportedAttribute,
+}
+
+impl DiagnosticKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            DiagnosticKind::SkippedGeom => "skipped geom",
+            DiagnosticKind::UnsupportedElement => "unsupported element",
+            DiagnosticKind::UnsupportedAttribute => "unsupported attribute",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// Name of the element the problem was found in, if it has one.
+    pub element: Option<String>,
+    pub message: String,
+    /// Byte range of the element in the parsed text. Like body source
+    /// ranges, the ranges of models returned by `parse_multi` do not
+    /// refer to the original text.
+    pub source_range: Option<Range<usize>>,
+}
+
+/// Problems the parser recovered from instead of failing.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParseDiagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseDiagnostics {
+    pub(crate) fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Diagnostics in the order they were found.
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Number of diagnostics of each kind, keyed by the kind's
+    /// description.
+    pub fn summary(&self) -> HashMap<String, usize> {
+        let mut summary = HashMap::new();
+        for diagnostic in &self.diagnostics {
+            *summary
+                .entry(diagnostic.kind.description().to_string())
+                .or_insert(0) += 1;
+        }
+        summary
+    }
+
+    /// The diagnostics as a JSON array for tools like editor plugins.
+    ///
+    /// Each entry has the `kind` description, the `element` name or
+    /// `null`, the `message` and the `source` byte range as an object with
+    /// a `start` and an `end`, or `null`.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json(&self) -> String {
+        let entries: Vec<_> = self
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                serde_json::json!({
+                    "kind": diagnostic.kind.description(),
+                    "element": diagnostic.element,
+                    "message": diagnostic.message,
+                    "source": diagnostic.source_range.as_ref().map(|range| {
+                        serde_json::json!({ "start": range.start, "end": range.end })
+                    }),
+                })
+            })
+            .collect();
+        serde_json::Value::Array(entries).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MJCFModel;
+
+    #[test]
+    fn summarize_diagnostics() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <visual/>
+                 <worldbody>
+                   <geom size="0.1" solref="0.02 1" fluidshape="ellipsoid"/>
+                   <geom type="box" size="1"/>
+                   <body mocap="true">
+                     <camera/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let summary = model.diagnostics().summary();
+        assert_eq!(summary.len(), 3);
+        assert_eq!(summary["unsupported attribute"], 3);
+        assert_eq!(summary["unsupported element"], 2);
+        assert_eq!(summary["skipped geom"], 1);
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn diagnostics_to_json() {
+        let text = r#"<mujoco><visual/><worldbody><geom name="crate" type="box" size="1"/></worldbody></mujoco>"#;
+        let model = MJCFModel::<f64>::parse_xml_string(text).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&model.diagnostics().to_json()).unwrap();
+        let entries = json.as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0]["kind"], "unsupported element");
+        assert_eq!(entries[0]["element"], serde_json::Value::Null);
+        assert_eq!(entries[0]["message"], "unsupported element 'mujoco/visual'");
+        let start = text.find("<visual/>").unwrap();
+        assert_eq!(entries[0]["source"]["start"], start);
+        assert_eq!(entries[0]["source"]["end"], start + "<visual/>".len());
+
+        assert_eq!(entries[1]["kind"], "skipped geom");
+        assert_eq!(entries[1]["element"], "crate");
+    }
+}
@@ -0,0 +1,274 @@
+use crate::mjcf_model::MJCFModel;
+use crate::tags::geom::{self, GeomUserData};
+use na::Real;
+use nalgebra as na;
+use ncollide3d::shape::{Ball, Capsule, ConvexHull, Cuboid, Cylinder, Plane, ShapeHandle};
+use nphysics3d::object::ColliderDesc;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Structural differences between two models.
+///
+/// Geom names are sorted so that the diff is deterministic.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModelDiff {
+    pub added_geoms: Vec<String>,
+    pub removed_geoms: Vec<String>,
+    pub changed_geoms: Vec<String>,
+    pub gravity_changed: bool,
+    pub options_changed: bool,
+}
+
+impl ModelDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_geoms.is_empty()
+            && self.removed_geoms.is_empty()
+            && self.changed_geoms.is_empty()
+            && !self.gravity_changed
+            && !self.options_changed
+    }
+}
+
+impl<N: Real> MJCFModel<N> {
+    /// Compare this model against `other`.
+    ///
+    /// Geoms are matched by name. Added geoms are those only present in
+    /// `other`.
+    pub fn diff(&self, other: &MJCFModel<N>) -> ModelDiff {
+        let mut diff = ModelDiff::default();
+
+        for (name, collider) in self.colliders() {
+            match other.get_collider(name) {
+                Some(other_collider) => {
+                    if !colliders_equal(collider, other_collider) {
+                        diff.changed_geoms.push(name.clone());
+                    }
+                }
+                None => diff.removed_geoms.push(name.clone()),
+            }
+        }
+        for name in other.colliders().keys() {
+            if self.get_collider(name).is_none() {
+                diff.added_geoms.push(name.clone());
+            }
+        }
+
+        diff.added_geoms.sort();
+        diff.removed_geoms.sort();
+        diff.changed_geoms.sort();
+
+        diff.gravity_changed = self.options().gravity != other.options().gravity;
+        diff.options_changed = self.options() != other.options();
+
+        diff
+    }
+
+    /// Hash of the structural content of the model: the names, shape
+    /// kinds, dimensions and positions of all geoms, the bodies and
+    /// joints, and the gravity.
+    ///
+    /// Elements are hashed sorted by name, so models parsed from the same
+    /// text always have the same fingerprint. The hash function of the
+    /// standard library may change between Rust releases, so fingerprints
+    /// should not be persisted across toolchains.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        let mut geom_names: Vec<_> = self.colliders().keys().collect();
+        geom_names.sort();
+        for name in geom_names {
+            let collider = &self.colliders()[name];
+            name.hash(&mut hasher);
+            self.geom_body(name).hash(&mut hasher);
+            self.geom_shape_kind(name).hash(&mut hasher);
+            hash_debug(&shape_dimensions(collider), &mut hasher);
+            hash_debug(collider.get_position(), &mut hasher);
+        }
+
+        let mut bodies: Vec<_> = self.bodies().values().collect();
+        bodies.sort_by(|a, b| a.name.cmp(&b.name));
+        for body in bodies {
+            body.name.hash(&mut hasher);
+            body.parent.hash(&mut hasher);
+            hash_debug(&body.position, &mut hasher);
+        }
+
+        let mut joint_names: Vec<_> = self.joints().keys().collect();
+        joint_names.sort();
+        for name in joint_names {
+            name.hash(&mut hasher);
+            self.joint_body(name).hash(&mut hasher);
+            hash_debug(&self.joints()[name], &mut hasher);
+        }
+
+        hash_debug(&self.options().gravity, &mut hasher);
+
+        hasher.finish()
+    }
+}
+
+/// Reals do not implement `Hash`, so values are hashed through their
+/// debug representation, which is exact for floats.
+fn hash_debug<T: fmt::Debug>(value: &T, hasher: &mut DefaultHasher) {
+    format!("{:?}", value).hash(hasher);
+}
+
+/// The parameters of the shapes `shapes_equal` compares.
+fn shape_dimensions<N: Real>(collider: &ColliderDesc<N>) -> Vec<N> {
+    if let Some(semi_axes) = geom::ellipsoid_semi_axes(collider) {
+        return semi_axes.iter().cloned().collect();
+    }
+
+    let shape = collider.get_shape();
+    if let Some(ball) = shape.as_shape::<Ball<N>>() {
+        return vec![ball.radius()];
+    }
+    if let Some(cuboid) = shape.as_shape::<Cuboid<N>>() {
+        return cuboid.half_extents().iter().cloned().collect();
+    }
+    if let Some(capsule) = shape.as_shape::<Capsule<N>>() {
+        return vec![capsule.radius(), capsule.half_height()];
+    }
+    if let Some(cylinder) = shape.as_shape::<Cylinder<N>>() {
+        return vec![cylinder.radius(), cylinder.half_height()];
+    }
+    if let Some(plane) = shape.as_shape::<Plane<N>>() {
+        return plane.normal().iter().cloned().collect();
+    }
+    if let Some(hull) = shape.as_shape::<ConvexHull<N>>() {
+        return hull
+            .points()
+            .iter()
+            .flat_map(|point| point.coords.iter().cloned())
+            .collect();
+    }
+
+    vec![]
+}
+
+fn colliders_equal<N: Real>(a: &ColliderDesc<N>, b: &ColliderDesc<N>) -> bool {
+    a.get_position() == b.get_position()
+        && a.get_density() == b.get_density()
+        && user_data_equal(a, b)
+        && shapes_equal(a.get_shape(), b.get_shape())
+}
+
+/// Document order and comments are not structural, so geoms that only
+/// moved within the document or were documented differently are not
+/// reported as changed.
+fn user_data_equal<N: Real>(a: &ColliderDesc<N>, b: &ColliderDesc<N>) -> bool {
+    let structural = |collider: &ColliderDesc<N>| {
+        GeomUserData::from_collider_desc(collider).map(|user_data| GeomUserData {
+            order: 0,
+            doc: None,
+            ..user_data.clone()
+        })
+    };
+    structural(a) == structural(b)
+}
+
+fn shapes_equal<N: Real>(a: &ShapeHandle<N>, b: &ShapeHandle<N>) -> bool {
+    if let (Some(a), Some(b)) = (a.as_shape::<Ball<N>>(), b.as_shape::<Ball<N>>()) {
+        return a.radius() == b.radius();
+    }
+    if let (Some(a), Some(b)) = (a.as_shape::<Cuboid<N>>(), b.as_shape::<Cuboid<N>>()) {
+        return a.half_extents() == b.half_extents();
+    }
+    if let (Some(a), Some(b)) = (a.as_shape::<Capsule<N>>(), b.as_shape::<Capsule<N>>()) {
+        return a.radius() == b.radius() && a.half_height() == b.half_height();
+    }
+    if let (Some(a), Some(b)) = (a.as_shape::<Cylinder<N>>(), b.as_shape::<Cylinder<N>>()) {
+        return a.radius() == b.radius() && a.half_height() == b.half_height();
+    }
+    if let (Some(a), Some(b)) = (a.as_shape::<Plane<N>>(), b.as_shape::<Plane<N>>()) {
+        return a.normal() == b.normal();
+    }
+    if let (Some(a), Some(b)) = (a.as_shape::<ConvexHull<N>>(), b.as_shape::<ConvexHull<N>>()) {
+        return a.points() == b.points();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_xml(radius: f64) -> String {
+        format!(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <geom name="ball" type="sphere" size="{}" pos="0 0 1"/>
+                   <geom name="crate" type="box" size="0.1 0.2 0.3" pos="1 0 0"/>
+                 </worldbody>
+               </mujoco>"#,
+            radius
+        )
+    }
+
+    #[test]
+    fn diff_identical_models() {
+        let a = MJCFModel::<f64>::parse_xml_string(&model_xml(0.5)).unwrap();
+        let b = MJCFModel::<f64>::parse_xml_string(&model_xml(0.5)).unwrap();
+        assert!(a.diff(&b).is_empty());
+    }
+
+    #[test]
+    fn diff_changed_geom_radius() {
+        let a = MJCFModel::<f64>::parse_xml_string(&model_xml(0.5)).unwrap();
+        let b = MJCFModel::<f64>::parse_xml_string(&model_xml(0.75)).unwrap();
+
+        let diff = a.diff(&b);
+        assert_eq!(diff.changed_geoms, vec![String::from("ball")]);
+        assert!(diff.added_geoms.is_empty());
+        assert!(diff.removed_geoms.is_empty());
+        assert!(!diff.gravity_changed);
+        assert!(!diff.options_changed);
+    }
+
+    #[test]
+    fn fingerprint_structure() {
+        let a = MJCFModel::<f64>::parse_xml_string(&model_xml(0.5)).unwrap();
+        let b = MJCFModel::<f64>::parse_xml_string(&model_xml(0.5)).unwrap();
+        let c = MJCFModel::<f64>::parse_xml_string(&model_xml(0.75)).unwrap();
+
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), c.fingerprint());
+    }
+
+    fn ellipsoid_xml(size: &str) -> String {
+        format!(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="egg" type="ellipsoid" size="{}"/>
+                 </worldbody>
+               </mujoco>"#,
+            size
+        )
+    }
+
+    #[test]
+    fn diff_ellipsoid() {
+        let a = MJCFModel::<f64>::parse_xml_string(&ellipsoid_xml("0.1 0.2 0.3")).unwrap();
+        let b = MJCFModel::<f64>::parse_xml_string(&ellipsoid_xml("0.1 0.2 0.3")).unwrap();
+        let c = MJCFModel::<f64>::parse_xml_string(&ellipsoid_xml("0.1 0.2 0.4")).unwrap();
+
+        assert!(a.diff(&b).is_empty());
+        assert_eq!(a.diff(&c).changed_geoms, vec![String::from("egg")]);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), c.fingerprint());
+    }
+
+    #[test]
+    fn diff_gravity() {
+        let a = MJCFModel::<f64>::parse_xml_string("<mujoco/>").unwrap();
+        let b = MJCFModel::<f64>::parse_xml_string(r#"<mujoco><option gravity="0 0 0"/></mujoco>"#)
+            .unwrap();
+
+        let diff = a.diff(&b);
+        assert!(diff.gravity_changed);
+        assert!(diff.options_changed);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::mjcf_model::MJCFModel;
+use crate::tags::geom::{self, GeomUserData};
+use na::{Isometry3, Point3, Point4, Real, Vector3};
+use nalgebra as na;
+use ncollide3d::procedural::{IndexBuffer, TriMesh};
+use ncollide3d::shape::{Ball, Capsule, ConvexHull, Cuboid, Cylinder, Plane, ShapeHandle};
+use ncollide3d::transformation::ToTriMesh;
+use std::io::{self, Write};
+
+/// Number of subdivisions around the axis of round shapes.
+const ROUND_SUBDIVISIONS: u32 = 16;
+
+/// Half of the side length of the quad planes are exported as. MuJoCo
+/// planes are infinite for collisions, so this only needs to be large
+/// compared to the rest of the scene.
+const PLANE_HALF_SIZE: f64 = 100.0;
+
+/// Engine independent description of a geom's shape, e.g. for renderers
+/// that do not use nphysics.
+///
+/// Every primitive carries its transform in the world frame and its
+/// color. Capsules and cylinders are aligned with the Z axis of their
+/// transform and planes face along it, like in MuJoCo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive<N: Real> {
+    Ball {
+        radius: N,
+        transform: Isometry3<N>,
+        rgba: Point4<N>,
+    },
+    Box {
+        half_extents: Vector3<N>,
+        transform: Isometry3<N>,
+        rgba: Point4<N>,
+    },
+    Capsule {
+        radius: N,
+        half_length: N,
+        transform: Isometry3<N>,
+        rgba: Point4<N>,
+    },
+    Cylinder {
+        radius: N,
+        half_length: N,
+        transform: Isometry3<N>,
+        rgba: Point4<N>,
+    },
+    Ellipsoid {
+        semi_axes: Vector3<N>,
+        transform: Isometry3<N>,
+        rgba: Point4<N>,
+    },
+    Plane {
+        transform: Isometry3<N>,
+        rgba: Point4<N>,
+    },
+}
+
+impl<N: Real> MJCFModel<N> {
+    /// Primitives of all geoms in document order. Geoms with shapes
+    /// that are not primitives are skipped.
+    pub fn primitives(&self) -> Vec<Primitive<N>> {
+        let mut primitives = Vec::new();
+        for (name, collider) in self.iter_geoms() {
+            let transform = self
+                .geom_world_position(name)
+                .expect("Geom body must be in the model");
+            let rgba = GeomUserData::from_collider_desc(collider)
+                .map(|user_data| user_data.rgba)
+                .unwrap_or_else(|| GeomUserData::default().rgba);
+            // ncollide's capsules and cylinders are aligned with the Y axis
+            let z_aligned = transform * geom::y_axis_to_z_axis().inverse();
+
+            let shape = collider.get_shape();
+            let primitive = if let Some(ball) = shape.as_shape::<Ball<N>>() {
+                Primitive::Ball {
+                    radius: ball.radius(),
+                    transform,
+                    rgba,
+                }
+            } else if let Some(cuboid) = shape.as_shape::<Cuboid<N>>() {
+                Primitive::Box {
+                    half_extents: *cuboid.half_extents(),
+                    transform,
+                    rgba,
+                }
+            } else if let Some(capsule) = shape.as_shape::<Capsule<N>>() {
+                Primitive::Capsule {
+                    radius: capsule.radius(),
+                    half_length: capsule.half_height(),
+                    transform: z_aligned,
+                    rgba,
+                }
+            } else if let Some(cylinder) = shape.as_shape::<Cylinder<N>>() {
+                Primitive::Cylinder {
+                    radius: cylinder.radius(),
+                    half_length: cylinder.half_height(),
+                    transform: z_aligned,
+                    rgba,
+                }
+            } else if let Some(semi_axes) = geom::ellipsoid_semi_axes(collider) {
+                Primitive::Ellipsoid {
+                    semi_axes,
+                    transform,
+                    rgba,
+                }
+            } else if shape.is_shape::<Plane<N>>() {
+                Primitive::Plane { transform, rgba }
+            } else {
+                continue;
+            };
+            primitives.push(primitive);
+        }
+        primitives
+    }
+
+    /// Write the colliders of the model at their world position as a
+    /// single Wavefront OBJ mesh, with one object per geom.
+    ///
+    /// This is meant for quick previews, so shapes are tessellated
+    /// coarsely. Colliders with unsupported shapes are skipped.
+    pub fn export_obj(&self, mut writer: impl Write) -> io::Result<()> {
+        // OBJ indices are global and start at one
+        let mut num_vertices = 1;
+
+        for (name, collider) in self.iter_geoms() {
+            let position = self
+                .geom_world_position(name)
+                .expect("Geom body must be in the model");
+            let (vertices, faces) = match tessellate(collider.get_shape()) {
+                Some(mesh) => mesh,
+                None => continue,
+            };
+
+            writeln!(writer, "o {}", name)?;
+            for vertex in &vertices {
+                let vertex = position * vertex;
+                writeln!(writer, "v {} {} {}", vertex.x, vertex.y, vertex.z)?;
+            }
+            for face in &faces {
+                writeln!(
+                    writer,
+                    "f {} {} {}",
+                    face.x as usize + num_vertices,
+                    face.y as usize + num_vertices,
+                    face.z as usize + num_vertices
+                )?;
+            }
+            num_vertices += vertices.len();
+        }
+
+        Ok(())
+    }
+}
+
+/// Vertices and triangles of the shape in its local frame.
+fn tessellate<N: Real>(shape: &ShapeHandle<N>) -> Option<(Vec<Point3<N>>, Vec<Point3<u32>>)> {
+    let mesh = if let Some(ball) = shape.as_shape::<Ball<N>>() {
+        ball.to_trimesh((ROUND_SUBDIVISIONS, ROUND_SUBDIVISIONS / 2))
+    } else if let Some(cuboid) = shape.as_shape::<Cuboid<N>>() {
+        cuboid.to_trimesh(())
+    } else if let Some(capsule) = shape.as_shape::<Capsule<N>>() {
+        capsule.to_trimesh((ROUND_SUBDIVISIONS, ROUND_SUBDIVISIONS / 2))
+    } else if let Some(cylinder) = shape.as_shape::<Cylinder<N>>() {
+        cylinder.to_trimesh(ROUND_SUBDIVISIONS)
+    } else if let Some(hull) = shape.as_shape::<ConvexHull<N>>() {
+        hull.to_trimesh(())
+    } else if let Some(plane) = shape.as_shape::<Plane<N>>() {
+        return Some(plane_quad(plane));
+    } else {
+        return None;
+    };
+
+    Some(mesh_vertices_and_faces(mesh))
+}
+
+fn mesh_vertices_and_faces<N: Real>(mesh: TriMesh<N>) -> (Vec<Point3<N>>, Vec<Point3<u32>>) {
+    let faces = match mesh.indices {
+        IndexBuffer::Unified(faces) => faces,
+        // Only the vertex positions are exported, so the normal and
+        // texture coordinate indices are dropped
+        IndexBuffer::Split(faces) => faces
+            .iter()
+            .map(|face| Point3::new(face.x.x, face.y.x, face.z.x))
+            .collect(),
+    };
+    (mesh.coords, faces)
+}
+
+/// A large square centered on the plane origin.
+fn plane_quad<N: Real>(plane: &Plane<N>) -> (Vec<Point3<N>>, Vec<Point3<u32>>) {
+    let normal = plane.normal();
+    // Any axis that is not parallel to the normal spans the plane
+    // together with the normal
+    let reference = if normal.x.abs() < na::convert(0.9) {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let u = normal.cross(&reference).normalize() * na::convert::<f64, N>(PLANE_HALF_SIZE);
+    let v = normal.cross(&u);
+
+    let origin = Point3::origin();
+    let vertices = vec![
+        origin - u - v,
+        origin + u - v,
+        origin + u + v,
+        origin - u + v,
+    ];
+    let faces = vec![Point3::new(0, 1, 2), Point3::new(0, 2, 3)];
+    (vertices, faces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn export_box() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="crate" type="box" size="0.1 0.2 0.3" pos="0 0 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mut obj = Vec::new();
+        model.export_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+
+        assert!(obj.starts_with("o crate\n"));
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 8);
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("f ")).count(),
+            12
+        );
+    }
+
+    #[test]
+    fn export_primitives() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="crate" type="box" size="0.1 0.2 0.3" pos="0 0 1"
+                         rgba="1 0 0 1"/>
+                   <body pos="1 0 0" euler="0 0 90">
+                     <geom name="ball" size="0.5" pos="0 2 0" rgba="0 0 1 0.5"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let primitives = model.primitives();
+        assert_eq!(primitives.len(), 2);
+        match primitives[0] {
+            Primitive::Box {
+                half_extents,
+                transform,
+                rgba,
+            } => {
+                assert_eq!(half_extents, Vector3::new(0.1, 0.2, 0.3));
+                assert_eq!(transform, Isometry3::translation(0.0, 0.0, 1.0));
+                assert_eq!(rgba, Point4::new(1.0, 0.0, 0.0, 1.0));
+            }
+            ref primitive => panic!("unexpected primitive {:?}", primitive),
+        }
+        match primitives[1] {
+            Primitive::Ball {
+                radius,
+                transform,
+                rgba,
+            } => {
+                assert_eq!(radius, 0.5);
+                // The body is turned by a quarter about Z, so the geom
+                // offset along Y points along -X in the world
+                assert_relative_eq!(
+                    transform.translation.vector,
+                    Vector3::new(-1.0, 0.0, 0.0),
+                    epsilon = 1e-10
+                );
+                assert_eq!(rgba, Point4::new(0.0, 0.0, 1.0, 0.5));
+            }
+            ref primitive => panic!("unexpected primitive {:?}", primitive),
+        }
+    }
+
+    #[test]
+    fn export_ellipsoid() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="egg" type="ellipsoid" size="0.1 0.2 0.3" pos="0 0 1"
+                         rgba="0 1 0 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let primitives = model.primitives();
+        assert_eq!(primitives.len(), 1);
+        match primitives[0] {
+            Primitive::Ellipsoid {
+                semi_axes,
+                transform,
+                rgba,
+            } => {
+                assert_eq!(semi_axes, Vector3::new(0.1, 0.2, 0.3));
+                assert_eq!(transform, Isometry3::translation(0.0, 0.0, 1.0));
+                assert_eq!(rgba, Point4::new(0.0, 1.0, 0.0, 1.0));
+            }
+            ref primitive => panic!("unexpected primitive {:?}", primitive),
+        }
+
+        let mut obj = Vec::new();
+        model.export_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+
+        assert!(obj.starts_with("o egg\n"));
+        assert!(obj.lines().any(|line| line.starts_with("v ")));
+        assert!(obj.lines().any(|line| line.starts_with("f ")));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::mjcf_model::{MJCFParseErrorKind, MJCFParseResult};
+use roxmltree;
+use std::fs;
+use std::path::Path;
+
+/// Read a model file, replacing every `include` element with the
+/// children of the root of the file it names.
+///
+/// Like in MuJoCo, included files are looked up relative to `directory`,
+/// the directory of the main model file, at any nesting level. Included
+/// files may include other files up to `max_depth` levels deep, which
+/// also stops include cycles.
+pub(crate) fn read_with_includes(
+    path: &Path,
+    directory: &Path,
+    max_depth: usize,
+) -> MJCFParseResult<String> {
+    let text = read_model_file(path)?;
+    expand_includes(&text, directory, 0, max_depth)
+}
+
+fn read_model_file(path: &Path) -> MJCFParseResult<String> {
+    fs::read_to_string(path).map_err(|error| {
+        MJCFParseErrorKind::ReadFile {
+            path: path.to_path_buf(),
+            error,
+        }
+        .into()
+    })
+}
+
+/// Expand the includes of a document read from a file `depth` includes
+/// below the main model file.
+fn expand_includes(
+    text: &str,
+    directory: &Path,
+    depth: usize,
+    max_depth: usize,
+) -> MJCFParseResult<String> {
+    let doc = roxmltree::Document::parse(text)?;
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut copied = 0;
+    for include in doc
+        .descendants()
+        .filter(|node| node.has_tag_name("include"))
+    {
+        let range = include.range();
+        // Includes nested in an include element are replaced along with
+        // it
+        if range.start < copied {
+            continue;
+        }
+        expanded.push_str(&text[copied..range.start]);
+        copied = range.end;
+
+        let file = include
+            .attribute("file")
+            .ok_or(MJCFParseErrorKind::IncludeWithoutFile)?;
+        if depth >= max_depth {
+            return Err(MJCFParseErrorKind::IncludeTooDeep(file.into()).into());
+        }
+        let included = read_model_file(&directory.join(file))?;
+        let included = expand_includes(&included, directory, depth + 1, max_depth)?;
+        expanded.push_str(&root_content(&included)?);
+    }
+    expanded.push_str(&text[copied..]);
+
+    Ok(expanded)
+}
+
+/// Text between the start and end tag of the `mujoco` root of a
+/// document.
+fn root_content(text: &str) -> MJCFParseResult<String> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    if !root.has_tag_name("mujoco") {
+        return Err(MJCFParseErrorKind::NotMuJoCoModel(root.tag_name().name().to_string()).into());
+    }
+
+    let element = &text[root.range()];
+    if element.ends_with("/>") {
+        return Ok(String::new());
+    }
+    // The start tag ends at the first '>' outside of an attribute value
+    let mut quote = None;
+    let mut start = element.len();
+    for (index, c) in element.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => {
+                start = index + 1;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let end = element.rfind("</").unwrap_or(start).max(start);
+    Ok(element[start..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mjcf_model::MJCFModel;
+    use crate::parse_options::ParseOptions;
+    use std::path::PathBuf;
+
+    /// Write the files into a fresh temporary directory and return it.
+    fn model_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("mjcf-parser-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (file, text) in files {
+            fs::write(directory.join(file), text).unwrap();
+        }
+        directory
+    }
+
+    #[test]
+    fn parse_included_geoms() {
+        let directory = model_dir(
+            "include",
+            &[
+                (
+                    "model.xml",
+                    r#"<mujoco>
+                         <worldbody>
+                           <geom name="floor" type="plane" size="1 1 1"/>
+                           <include file="ball.xml"/>
+                         </worldbody>
+                       </mujoco>"#,
+                ),
+                (
+                    "ball.xml",
+                    r#"<mujoco model="ball">
+                         <body name="ball" pos="0 0 1">
+                           <geom name="ball" size="0.1"/>
+                         </body>
+                       </mujoco>"#,
+                ),
+            ],
+        );
+
+        let model = MJCFModel::<f64>::parse_xml_file(directory.join("model.xml")).unwrap();
+        assert!(model.get_collider("floor").is_some());
+        assert!(model.get_collider("ball").is_some());
+        assert_eq!(model.geom_body("ball"), Some("ball"));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn reject_includes_nested_too_deep() {
+        let directory = model_dir(
+            "include-depth",
+            &[
+                (
+                    "model.xml",
+                    r#"<mujoco><worldbody><include file="a.xml"/></worldbody></mujoco>"#,
+                ),
+                ("a.xml", r#"<mujoco><include file="b.xml"/></mujoco>"#),
+                ("b.xml", r#"<mujoco><include file="c.xml"/></mujoco>"#),
+                (
+                    "c.xml",
+                    r#"<mujoco><geom name="deep" size="0.1"/></mujoco>"#,
+                ),
+            ],
+        );
+        let path = directory.join("model.xml");
+
+        let options = ParseOptions::builder().max_include_depth(2).build();
+        let error = MJCFModel::<f64>::parse_xml_file_with_options(&path, &options).unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::IncludeTooDeep(file) => assert_eq!(file, PathBuf::from("c.xml")),
+            kind => panic!("unexpected error {:?}", kind),
+        }
+
+        let options = ParseOptions::builder().max_include_depth(3).build();
+        let model = MJCFModel::<f64>::parse_xml_file_with_options(&path, &options).unwrap();
+        assert!(model.get_collider("deep").is_some());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn reject_include_cycles() {
+        let directory = model_dir(
+            "include-cycle",
+            &[(
+                "model.xml",
+                r#"<mujoco><include file="model.xml"/></mujoco>"#,
+            )],
+        );
+
+        let error = MJCFModel::<f64>::parse_xml_file(directory.join("model.xml")).unwrap_err();
+        assert!(match error.kind {
+            MJCFParseErrorKind::IncludeTooDeep(_) => true,
+            _ => false,
+        });
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn root_content_of_empty_model() {
+        assert_eq!(root_content("<mujoco/>").unwrap(), "");
+        assert_eq!(
+            root_content(r#"<mujoco model="a>b"><geom/></mujoco>"#).unwrap(),
+            "<geom/>"
+        );
+    }
+}
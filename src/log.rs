@@ -1,11 +1,11 @@
 use crate::built_info;
-use crossbeam::atomic::AtomicCell;
 use slog;
 use slog::Drain;
 use slog_stdlog;
+use std::sync::RwLock;
 
 lazy_static! {
-    pub static ref LOG: AtomicCell<slog::Logger> = AtomicCell::new(create_root_logger(None));
+    pub static ref LOG: RwLock<slog::Logger> = RwLock::new(create_root_logger(None));
 }
 
 fn create_root_logger<L: Into<Option<slog::Logger>>>(logger: L) -> slog::Logger {
@@ -19,5 +19,9 @@ fn create_root_logger<L: Into<Option<slog::Logger>>>(logger: L) -> slog::Logger
 }
 
 pub fn set_root_logger<L: Into<slog::Logger>>(logger: L) {
-    LOG.store(create_root_logger(Some(logger.into())));
+    *LOG.write().unwrap() = create_root_logger(Some(logger.into()));
+}
+
+pub fn get_root_logger() -> slog::Logger {
+    LOG.read().unwrap().clone()
 }
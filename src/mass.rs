@@ -0,0 +1,443 @@
+use crate::mjcf_model::{MJCFModel, WORLDBODY_NAME};
+use crate::tags::geom;
+use na::{Isometry3, Matrix3, Point3, Real, Vector3};
+use nalgebra as na;
+use ncollide3d::shape::{Ball, Capsule, Cuboid, Cylinder};
+use nphysics3d::object::ColliderDesc;
+
+/// Mass properties of a body, expressed in the body frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MassProperties<N: Real> {
+    pub mass: N,
+    pub center_of_mass: Point3<N>,
+    /// Inertia tensor about the center of mass.
+    pub inertia: Matrix3<N>,
+}
+
+impl<N: Real> MassProperties<N> {
+    fn zero() -> Self {
+        MassProperties {
+            mass: N::zero(),
+            center_of_mass: Point3::origin(),
+            inertia: Matrix3::zeros(),
+        }
+    }
+
+    /// The same mass properties expressed in the frame that `position`
+    /// is relative to.
+    pub(crate) fn transformed(&self, position: &Isometry3<N>) -> Self {
+        let rotation = position.rotation.to_rotation_matrix();
+        MassProperties {
+            mass: self.mass,
+            center_of_mass: position * self.center_of_mass,
+            inertia: rotation.matrix() * self.inertia * rotation.matrix().transpose(),
+        }
+    }
+}
+
+/// Mass properties of parts given in the same frame combined into one,
+/// with the inertia moved to the combined center of mass.
+pub(crate) fn combine<N: Real>(parts: &[MassProperties<N>]) -> MassProperties<N> {
+    let mut properties = MassProperties::zero();
+    properties.mass = parts.iter().fold(N::zero(), |mass, part| mass + part.mass);
+    if properties.mass > N::zero() {
+        let weighted = parts.iter().fold(Vector3::zeros(), |sum, part| {
+            sum + part.center_of_mass.coords * part.mass
+        });
+        properties.center_of_mass = Point3::from(weighted / properties.mass);
+    }
+    for part in parts {
+        let offset = part.center_of_mass - properties.center_of_mass;
+        properties.inertia += part.inertia
+            + (Matrix3::identity() * offset.norm_squared() - offset * offset.transpose())
+                * part.mass;
+    }
+    properties
+}
+
+impl<N: Real> MJCFModel<N> {
+    /// Compute the mass properties of a body from the density and shape of
+    /// its geoms.
+    ///
+    /// Planes have no volume and do not contribute. The compiler's
+    /// `boundmass` and `boundinertia` are applied to the result of every
+    /// body except the world, so that tiny geoms do not produce unstable
+    /// masses.
+    pub fn body_mass_properties(&self, name: &str) -> Option<MassProperties<N>> {
+        let body = self.get_body(name)?;
+
+        let geoms: Vec<_> = body
+            .geoms
+            .iter()
+            .filter_map(|geom_name| geom_mass_properties(&self.colliders()[geom_name]))
+            .collect();
+
+        let mut properties = combine(&geoms);
+
+        if name != WORLDBODY_NAME {
+            let compiler = self.compiler();
+            properties.mass = properties.mass.max(compiler.boundmass);
+            for i in 0..3 {
+                properties.inertia[(i, i)] = properties.inertia[(i, i)].max(compiler.boundinertia);
+            }
+        }
+
+        Some(properties)
+    }
+
+    /// Volume of a geom's shape. Planes have no volume and return `None`.
+    pub fn geom_volume(&self, name: &str) -> Option<N> {
+        shape_volume(self.get_collider(name)?)
+    }
+
+    /// Inertia tensor of a geom about its center, computed from its
+    /// density and shape and expressed in the axes of its body's frame.
+    /// Planes have no volume and return `None`.
+    pub fn geom_inertia(&self, name: &str) -> Option<Matrix3<N>> {
+        geom_mass_properties(self.get_collider(name)?).map(|properties| properties.inertia)
+    }
+
+    /// Sum of the masses of all bodies except the world body.
+    pub fn total_mass(&self) -> N {
+        self.iter_bodies()
+            .filter(|body| body.name != WORLDBODY_NAME)
+            .map(|body| {
+                self.body_mass_properties(&body.name)
+                    .expect("Body must be in the model")
+                    .mass
+            })
+            .fold(N::zero(), |total, mass| total + mass)
+    }
+
+    /// Mass weighted average of the world positions of the centers of
+    /// mass of all bodies, including the geoms of the world body.
+    ///
+    /// A model without mass has its center of mass at the origin.
+    pub fn center_of_mass(&self) -> Point3<N> {
+        let mut mass = N::zero();
+        let mut weighted = Vector3::zeros();
+        for body in self.iter_bodies() {
+            let properties = self
+                .body_mass_properties(&body.name)
+                .expect("Body must be in the model");
+            let body_position = self
+                .body_world_position(&body.name)
+                .expect("Body ancestors must be in the model");
+            mass += properties.mass;
+            weighted += (body_position * properties.center_of_mass).coords * properties.mass;
+        }
+
+        if mass > N::zero() {
+            Point3::from(weighted / mass)
+        } else {
+            Point3::origin()
+        }
+    }
+}
+
+/// Mass properties of a single geom in its body's frame.
+fn geom_mass_properties<N: Real>(collider: &ColliderDesc<N>) -> Option<MassProperties<N>> {
+    let (mass, principal_inertia) = shape_mass_properties(collider)?;
+
+    let position = collider.get_position();
+    let rotation = position.rotation.to_rotation_matrix();
+    let inertia = rotation.matrix()
+        * Matrix3::from_diagonal(&principal_inertia)
+        * rotation.matrix().transpose();
+
+    Some(MassProperties {
+        mass,
+        center_of_mass: Point3::from(position.translation.vector),
+        inertia,
+    })
+}
+
+fn shape_volume<N: Real>(collider: &ColliderDesc<N>) -> Option<N> {
+    let pi = N::pi();
+    let four_thirds = na::convert::<f64, N>(4.0 / 3.0);
+
+    // The convex hull of an ellipsoid is only an approximation, so its
+    // exact volume is used instead
+    if let Some(semi_axes) = geom::ellipsoid_semi_axes(collider) {
+        return Some(four_thirds * pi * semi_axes.x * semi_axes.y * semi_axes.z);
+    }
+    let shape = collider.get_shape();
+
+    if let Some(ball) = shape.as_shape::<Ball<N>>() {
+        let r = ball.radius();
+        return Some(four_thirds * pi * r * r * r);
+    }
+    if let Some(cuboid) = shape.as_shape::<Cuboid<N>>() {
+        let extents = cuboid.half_extents();
+        return Some(na::convert::<f64, N>(8.0) * extents.x * extents.y * extents.z);
+    }
+    if let Some(capsule) = shape.as_shape::<Capsule<N>>() {
+        let r = capsule.radius();
+        let h = capsule.half_height();
+        return Some(pi * r * r * h * na::convert(2.0) + four_thirds * pi * r * r * r);
+    }
+    if let Some(cylinder) = shape.as_shape::<Cylinder<N>>() {
+        let r = cylinder.radius();
+        return Some(pi * r * r * cylinder.half_height() * na::convert(2.0));
+    }
+
+    None
+}
+
+/// Mass and principal moments of inertia of a collider's shape centered
+/// at its origin.
+fn shape_mass_properties<N: Real>(collider: &ColliderDesc<N>) -> Option<(N, Vector3<N>)> {
+    let pi = N::pi();
+    let density = collider.get_density();
+
+    if let Some(semi_axes) = geom::ellipsoid_semi_axes(collider) {
+        let mass = density * shape_volume(collider)?;
+        let fifth = mass / na::convert(5.0);
+        let (a2, b2, c2) = (
+            semi_axes.x * semi_axes.x,
+            semi_axes.y * semi_axes.y,
+            semi_axes.z * semi_axes.z,
+        );
+        return Some((
+            mass,
+            Vector3::new(fifth * (b2 + c2), fifth * (a2 + c2), fifth * (a2 + b2)),
+        ));
+    }
+    let shape = collider.get_shape();
+
+    if let Some(ball) = shape.as_shape::<Ball<N>>() {
+        let r = ball.radius();
+        let mass = density * na::convert::<f64, N>(4.0 / 3.0) * pi * r * r * r;
+        let moment = na::convert::<f64, N>(0.4) * mass * r * r;
+        return Some((mass, Vector3::repeat(moment)));
+    }
+    if let Some(cuboid) = shape.as_shape::<Cuboid<N>>() {
+        let extents = cuboid.half_extents();
+        let mass = density * na::convert::<f64, N>(8.0) * extents.x * extents.y * extents.z;
+        let third = mass / na::convert(3.0);
+        let (x2, y2, z2) = (
+            extents.x * extents.x,
+            extents.y * extents.y,
+            extents.z * extents.z,
+        );
+        return Some((
+            mass,
+            Vector3::new(third * (y2 + z2), third * (x2 + z2), third * (x2 + y2)),
+        ));
+    }
+    if let Some(capsule) = shape.as_shape::<Capsule<N>>() {
+        // The capsule axis is Y. Split it into a cylinder and the two
+        // hemispheres, which together form one sphere.
+        let r = capsule.radius();
+        let h = capsule.half_height();
+        let cylinder_mass = density * pi * r * r * h * na::convert(2.0);
+        let sphere_mass = density * na::convert::<f64, N>(4.0 / 3.0) * pi * r * r * r;
+
+        let two_fifths = na::convert::<f64, N>(0.4);
+        let axial = cylinder_mass * r * r / na::convert(2.0) + sphere_mass * two_fifths * r * r;
+        let transverse = cylinder_mass * (r * r / na::convert(4.0) + h * h / na::convert(3.0))
+            + sphere_mass * (two_fifths * r * r + h * h + na::convert::<f64, N>(0.75) * h * r);
+
+        return Some((
+            cylinder_mass + sphere_mass,
+            Vector3::new(transverse, axial, transverse),
+        ));
+    }
+    if let Some(cylinder) = shape.as_shape::<Cylinder<N>>() {
+        // The cylinder axis is Y
+        let r = cylinder.radius();
+        let h = cylinder.half_height();
+        let mass = density * pi * r * r * h * na::convert(2.0);
+        let axial = mass * r * r / na::convert(2.0);
+        let transverse = mass * (r * r / na::convert(4.0) + h * h / na::convert(3.0));
+        return Some((mass, Vector3::new(transverse, axial, transverse)));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn box_mass_properties() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="crate">
+                     <geom type="box" size="0.5 1 1.5" density="2"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let properties = model.body_mass_properties("crate").unwrap();
+        assert_relative_eq!(properties.mass, 12.0);
+        assert_relative_eq!(properties.inertia[(0, 0)], 4.0 * (1.0 + 2.25));
+        assert_relative_eq!(properties.inertia[(1, 1)], 4.0 * (0.25 + 2.25));
+        assert_relative_eq!(properties.inertia[(2, 2)], 4.0 * (0.25 + 1.0));
+    }
+
+    #[test]
+    fn unit_box_inertia() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <body>
+                     <geom name="crate" type="box" size="0.5 0.5 0.5" density="3"/>
+                     <geom name="tilted" type="box" size="0.5 0.5 0.5" density="3"
+                           euler="30 45 60"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        // A cube of mass m and side s has moments m * s^2 / 6, which do
+        // not change when it is rotated
+        let expected = Matrix3::identity() * 3.0 / 6.0;
+        assert_relative_eq!(model.geom_inertia("crate").unwrap(), expected);
+        assert_relative_eq!(
+            model.geom_inertia("tilted").unwrap(),
+            expected,
+            epsilon = 1e-12
+        );
+        assert_eq!(model.geom_inertia("floor"), None);
+        assert_eq!(model.geom_inertia("missing"), None);
+    }
+
+    #[test]
+    fn cylinder_inertia() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body>
+                     <geom name="can" type="cylinder" size="1 1" density="1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        // The cylinder is aligned with the Z axis of the body
+        let mass = 2.0 * std::f64::consts::PI;
+        let transverse = mass * (1.0 / 4.0 + 1.0 / 3.0);
+        assert_relative_eq!(
+            model.geom_inertia("can").unwrap(),
+            Matrix3::from_diagonal(&Vector3::new(transverse, transverse, mass / 2.0)),
+            epsilon = 1e-12
+        );
+    }
+
+    #[test]
+    fn geom_volumes() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ball" size="1"/>
+                   <geom name="crate" type="box" size="0.5 1 1.5"/>
+                   <geom name="pill" type="capsule" size="1 1"/>
+                   <geom name="can" type="cylinder" size="1 1.5"/>
+                   <geom name="egg" type="ellipsoid" size="1 2 3"/>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let pi = std::f64::consts::PI;
+        assert_relative_eq!(model.geom_volume("ball").unwrap(), 4.0 / 3.0 * pi);
+        assert_relative_eq!(model.geom_volume("crate").unwrap(), 6.0);
+        assert_relative_eq!(
+            model.geom_volume("pill").unwrap(),
+            2.0 * pi + 4.0 / 3.0 * pi
+        );
+        assert_relative_eq!(model.geom_volume("can").unwrap(), 3.0 * pi);
+        assert_relative_eq!(model.geom_volume("egg").unwrap(), 8.0 * pi);
+        assert_eq!(model.geom_volume("floor"), None);
+        assert_eq!(model.geom_volume("missing"), None);
+    }
+
+    #[test]
+    fn ellipsoid_mass_properties() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="egg">
+                     <geom type="ellipsoid" size="1 2 3" density="2"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let mass = 2.0 * 8.0 * std::f64::consts::PI;
+        let properties = model.body_mass_properties("egg").unwrap();
+        assert_relative_eq!(properties.mass, mass);
+        assert_relative_eq!(properties.inertia[(0, 0)], mass / 5.0 * (4.0 + 9.0));
+        assert_relative_eq!(properties.inertia[(1, 1)], mass / 5.0 * (1.0 + 9.0));
+        assert_relative_eq!(properties.inertia[(2, 2)], mass / 5.0 * (1.0 + 4.0));
+    }
+
+    #[test]
+    fn center_of_mass_of_equal_masses() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom type="plane" size="1 1 1"/>
+                   <geom size="0.1" pos="1 0 0"/>
+                   <body pos="0 2 1">
+                     <geom size="0.1" pos="-1 0 0"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_relative_eq!(model.center_of_mass(), Point3::new(0.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn scale_to_total_mass() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <compiler settotalmass="3"/>
+                 <worldbody>
+                   <geom type="plane" size="1 1 1"/>
+                   <body>
+                     <geom type="box" size="1 1 1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_relative_eq!(model.total_mass(), 3.0);
+    }
+
+    #[test]
+    fn clamp_to_compiler_bounds() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <compiler boundmass="5" boundinertia="0.5"/>
+                 <worldbody>
+                   <body name="pebble">
+                     <geom size="0.01"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let properties = model.body_mass_properties("pebble").unwrap();
+        assert_relative_eq!(properties.mass, 5.0);
+        assert_relative_eq!(properties.inertia, Matrix3::identity() * 0.5);
+
+        let world = model.body_mass_properties(WORLDBODY_NAME).unwrap();
+        assert_relative_eq!(world.mass, 0.0);
+    }
+}
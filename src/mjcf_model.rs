@@ -0,0 +1,2353 @@
+use crate::diagnostics::{Diagnostic, DiagnosticKind, ParseDiagnostics};
+use crate::include;
+use crate::log;
+use crate::parse_options::ParseOptions;
+use crate::tags;
+use crate::tags::body::{self, BodyDesc, BodyError, BodyNode};
+use crate::tags::compiler::{self, CompilerError, CompilerOptions, ModelMeta};
+use crate::tags::default::{self, DefaultError, Defaults, MAIN_CLASS};
+use crate::tags::geom::{self, GeomError, GeomUserData, ShapeKind};
+use crate::tags::joint::{self, JointDesc, JointError};
+use crate::tags::light::{self, LightDesc, LightError};
+use crate::tags::material::{self, MaterialDesc, MaterialError};
+use crate::tags::mesh::{self, MeshAsset, MeshError};
+use crate::tags::option::{self, OptionConfig, OptionError};
+use crate::tags::site::{self, SiteDesc, SiteError};
+use crate::tags::texture::{self, TextureAsset, TextureError};
+use na::{Isometry3, Point3, Real, Unit, Vector3};
+use nalgebra as na;
+use ncollide3d::shape::ShapeHandle;
+use nphysics3d::material::{BasicMaterial, MaterialHandle};
+use nphysics3d::object::ColliderDesc;
+use roxmltree;
+use slog;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum MJCFParseErrorKind {
+    BadXML(roxmltree::Error),
+    NotMuJoCoModel(String),
+    Compiler(CompilerError),
+    Option(OptionError),
+    Default(DefaultError),
+    Geom(GeomError),
+    Joint(JointError),
+    Light(LightError),
+    Material(MaterialError),
+    Mesh(MeshError),
+    Site(SiteError),
+    Texture(TextureError),
+    UnknownClass(String),
+    DuplicateGeomName(String),
+    DuplicateJointName(String),
+    DuplicateLightName(String),
+    DuplicateMaterialName(String),
+    DuplicateMeshName(String),
+    DuplicateSiteName(String),
+    DuplicateTextureName(String),
+    UnknownMaterial {
+        geom: String,
+        material: String,
+    },
+    DuplicateBodyName(String),
+    /// Path of an element the parser does not know.
+    UnknownElement(String),
+    Body {
+        body: String,
+        error: BodyError,
+    },
+    /// File named by an include nested deeper than the
+    /// `max_include_depth` parse option.
+    IncludeTooDeep(PathBuf),
+    IncludeWithoutFile,
+    ReadFile {
+        path: PathBuf,
+        error: io::Error,
+    },
+}
+
+#[derive(Debug)]
+pub struct MJCFParseError {
+    pub kind: MJCFParseErrorKind,
+}
+
+impl fmt::Display for MJCFParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            MJCFParseErrorKind::BadXML(error) => write!(f, "invalid XML: {}", error),
+            MJCFParseErrorKind::NotMuJoCoModel(tag_name) => write!(
+                f,
+                "expected root element 'mujoco', but found '{}'",
+                tag_name
+            ),
+            MJCFParseErrorKind::Compiler(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Option(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Default(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Geom(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Joint(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Light(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Material(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Mesh(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Site(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::Texture(error) => write!(f, "{}", error),
+            MJCFParseErrorKind::UnknownClass(class) => {
+                write!(f, "default class '{}' is not defined", class)
+            }
+            MJCFParseErrorKind::DuplicateGeomName(name) => {
+                write!(f, "geom name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::DuplicateJointName(name) => {
+                write!(f, "joint name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::DuplicateLightName(name) => {
+                write!(f, "light name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::DuplicateMaterialName(name) => {
+                write!(f, "material name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::DuplicateMeshName(name) => {
+                write!(f, "mesh name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::DuplicateSiteName(name) => {
+                write!(f, "site name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::DuplicateTextureName(name) => {
+                write!(f, "texture name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::UnknownMaterial { geom, material } => {
+                write!(
+                    f,
+                    "geom '{}' references unknown material '{}'",
+                    geom, material
+                )
+            }
+            MJCFParseErrorKind::DuplicateBodyName(name) => {
+                write!(f, "body name '{}' is used more than once", name)
+            }
+            MJCFParseErrorKind::UnknownElement(path) => {
+                write!(f, "unknown element '{}'", path)
+            }
+            MJCFParseErrorKind::Body { body, error } => {
+                write!(f, "body '{}': {}", body, error)
+            }
+            MJCFParseErrorKind::IncludeTooDeep(file) => {
+                write!(f, "include of '{}' is nested too deep", file.display())
+            }
+            MJCFParseErrorKind::IncludeWithoutFile => {
+                write!(f, "include element is missing the 'file' attribute")
+            }
+            MJCFParseErrorKind::ReadFile { path, error } => {
+                write!(f, "failed to read '{}': {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl Error for MJCFParseError {}
+
+impl From<MJCFParseErrorKind> for MJCFParseError {
+    fn from(kind: MJCFParseErrorKind) -> Self {
+        MJCFParseError { kind }
+    }
+}
+
+impl From<roxmltree::Error> for MJCFParseError {
+    fn from(error: roxmltree::Error) -> Self {
+        MJCFParseErrorKind::BadXML(error).into()
+    }
+}
+
+impl From<CompilerError> for MJCFParseError {
+    fn from(error: CompilerError) -> Self {
+        MJCFParseErrorKind::Compiler(error).into()
+    }
+}
+
+impl From<OptionError> for MJCFParseError {
+    fn from(error: OptionError) -> Self {
+        MJCFParseErrorKind::Option(error).into()
+    }
+}
+
+impl From<DefaultError> for MJCFParseError {
+    fn from(error: DefaultError) -> Self {
+        MJCFParseErrorKind::Default(error).into()
+    }
+}
+
+impl From<GeomError> for MJCFParseError {
+    fn from(error: GeomError) -> Self {
+        MJCFParseErrorKind::Geom(error).into()
+    }
+}
+
+impl From<JointError> for MJCFParseError {
+    fn from(error: JointError) -> Self {
+        MJCFParseErrorKind::Joint(error).into()
+    }
+}
+
+impl From<LightError> for MJCFParseError {
+    fn from(error: LightError) -> Self {
+        MJCFParseErrorKind::Light(error).into()
+    }
+}
+
+impl From<MaterialError> for MJCFParseError {
+    fn from(error: MaterialError) -> Self {
+        MJCFParseErrorKind::Material(error).into()
+    }
+}
+
+impl From<MeshError> for MJCFParseError {
+    fn from(error: MeshError) -> Self {
+        MJCFParseErrorKind::Mesh(error).into()
+    }
+}
+
+impl From<SiteError> for MJCFParseError {
+    fn from(error: SiteError) -> Self {
+        MJCFParseErrorKind::Site(error).into()
+    }
+}
+
+impl From<TextureError> for MJCFParseError {
+    fn from(error: TextureError) -> Self {
+        MJCFParseErrorKind::Texture(error).into()
+    }
+}
+
+pub type MJCFParseResult<T> = Result<T, MJCFParseError>;
+
+/// A geom with the given name is not part of the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownGeomError(pub String);
+
+impl fmt::Display for UnknownGeomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "model has no geom named '{}'", self.0)
+    }
+}
+
+impl Error for UnknownGeomError {}
+
+/// Name MuJoCo gives to the implicit body of the `worldbody` element.
+pub const WORLDBODY_NAME: &str = "world";
+
+/// Geom attributes that only affect the dynamics of the geom's body.
+const BODY_ATTRIBUTES: &[&str] = &["density", "mass"];
+
+/// Geoms of the world body never move, so attributes describing how
+/// their body moves are a common authoring mistake.
+fn warn_static_geom_attributes(logger: &slog::Logger, geom_node: &roxmltree::Node) {
+    for &attribute in BODY_ATTRIBUTES {
+        if geom_node.has_attribute(attribute) {
+            warn!(logger, "World body geoms are static, so their mass is ignored";
+                  "geom" => geom_node.attribute("name"), "attribute" => attribute);
+        }
+    }
+}
+
+/// Name of the element `parse_multi` wraps concatenated models in.
+const MULTI_MODEL_ROOT: &str = "mjcf-parser-models";
+
+/// Remove all `<?xml ... ?>` declarations. They are only allowed at the
+/// very start of a document.
+fn strip_xml_declarations(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<?xml") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start..].find("?>") {
+            Some(end) => &rest[start + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+pub struct MJCFModel<N: Real> {
+    model_name: String,
+    parse_options: ParseOptions,
+    compiler: CompilerOptions<N>,
+    options: OptionConfig<N>,
+    defaults: Defaults,
+    /// Classes set by the `childclass` of the bodies currently being
+    /// parsed. The innermost body is last.
+    childclass_stack: Vec<String>,
+    shapes: HashMap<String, ShapeHandle<N>>,
+    colliders: HashMap<String, ColliderDesc<N>>,
+    materials: HashMap<String, MaterialHandle<N>>,
+    material_descs: HashMap<String, MaterialDesc<N>>,
+    textures: HashMap<String, TextureAsset<N>>,
+    meshes: HashMap<String, MeshAsset<N>>,
+    bodies: HashMap<String, BodyDesc<N>>,
+    /// Hierarchy of `bodies`, rebuilt once the bodies are complete.
+    tree: BodyNode<N>,
+    geom_bodies: HashMap<String, String>,
+    shape_kinds: HashMap<String, ShapeKind>,
+    joints: HashMap<String, JointDesc<N>>,
+    joint_bodies: HashMap<String, String>,
+    sites: HashMap<String, SiteDesc<N>>,
+    site_bodies: HashMap<String, String>,
+    /// Lights of the world body. Their positions are in the world frame.
+    lights: HashMap<String, LightDesc<N>>,
+    /// Lights attached to other bodies, which move with them.
+    body_lights: HashMap<String, LightDesc<N>>,
+    light_bodies: HashMap<String, String>,
+    /// Names of the assets referenced by geoms and materials, whether or
+    /// not they are defined.
+    referenced_assets: HashSet<String>,
+    /// Visual geoms dropped because of the compiler's `discardvisual`.
+    /// Their names still have to be unique.
+    discarded_geoms: HashSet<String>,
+    num_geoms: usize,
+    num_bodies: usize,
+    num_joints: usize,
+    num_sites: usize,
+    num_lights: usize,
+    num_textures: usize,
+    diagnostics: ParseDiagnostics,
+    user_data: Option<Box<dyn Any + Send + Sync>>,
+}
+
+impl<N: Real> MJCFModel<N> {
+    /// Parse a model, logging to the global root logger.
+    pub fn parse_xml_string(text: &str) -> MJCFParseResult<MJCFModel<N>> {
+        Self::parse_xml_string_with_logger(text, &log::get_root_logger())
+    }
+
+    /// Parse a model, logging to `logger` instead of the global root
+    /// logger.
+    pub fn parse_xml_string_with_logger(
+        text: &str,
+        logger: &slog::Logger,
+    ) -> MJCFParseResult<MJCFModel<N>> {
+        Self::parse(text, logger, ParseOptions::default())
+    }
+
+    /// Parse a model with non-default parse options, logging to the
+    /// global root logger.
+    pub fn parse_xml_string_with_options(
+        text: &str,
+        options: &ParseOptions,
+    ) -> MJCFParseResult<MJCFModel<N>> {
+        Self::parse(text, &log::get_root_logger(), options.clone())
+    }
+
+    /// Parse a model file, logging to the global root logger.
+    ///
+    /// Unlike the string entry points, which leave `include` elements
+    /// unresolved, this replaces them with the contents of the files
+    /// they name, relative to the directory of `path`.
+    pub fn parse_xml_file<P: AsRef<Path>>(path: P) -> MJCFParseResult<MJCFModel<N>> {
+        Self::parse_xml_file_with_options(path, &ParseOptions::default())
+    }
+
+    /// Parse a model file with non-default parse options, logging to the
+    /// global root logger.
+    pub fn parse_xml_file_with_options<P: AsRef<Path>>(
+        path: P,
+        options: &ParseOptions,
+    ) -> MJCFParseResult<MJCFModel<N>> {
+        let path = path.as_ref();
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let text = include::read_with_includes(path, directory, options.max_include_depth)?;
+        Self::parse(&text, &log::get_root_logger(), options.clone())
+    }
+
+    /// A model containing only the world body.
+    fn empty(parse_options: ParseOptions) -> MJCFModel<N> {
+        let mut mjcf_model = MJCFModel {
+            model_name: String::from("MuJoCo Model"),
+            parse_options,
+            compiler: CompilerOptions::default(),
+            options: OptionConfig::default(),
+            defaults: Defaults::default(),
+            childclass_stack: vec![],
+            shapes: HashMap::new(),
+            colliders: HashMap::new(),
+            materials: HashMap::new(),
+            material_descs: HashMap::new(),
+            textures: HashMap::new(),
+            meshes: HashMap::new(),
+            bodies: HashMap::new(),
+            tree: BodyNode {
+                name: WORLDBODY_NAME.to_string(),
+                position: Isometry3::identity(),
+                geoms: vec![],
+                sites: vec![],
+                joints: vec![],
+                children: vec![],
+            },
+            geom_bodies: HashMap::new(),
+            shape_kinds: HashMap::new(),
+            joints: HashMap::new(),
+            joint_bodies: HashMap::new(),
+            sites: HashMap::new(),
+            site_bodies: HashMap::new(),
+            lights: HashMap::new(),
+            body_lights: HashMap::new(),
+            light_bodies: HashMap::new(),
+            referenced_assets: HashSet::new(),
+            discarded_geoms: HashSet::new(),
+            num_geoms: 0,
+            num_bodies: 0,
+            num_joints: 0,
+            num_sites: 0,
+            num_lights: 0,
+            num_textures: 0,
+            diagnostics: ParseDiagnostics::default(),
+            user_data: None,
+        };
+        mjcf_model.bodies.insert(
+            WORLDBODY_NAME.to_string(),
+            BodyDesc::new(WORLDBODY_NAME.to_string(), None, Isometry3::identity(), 0),
+        );
+
+        mjcf_model
+    }
+
+    /// Parse several models concatenated into a single string, such as
+    /// multiple files appended to each other.
+    ///
+    /// The models are returned in document order. XML declarations in
+    /// front of each model are allowed.
+    pub fn parse_multi(text: &str) -> MJCFParseResult<Vec<MJCFModel<N>>> {
+        let logger = log::get_root_logger();
+
+        // An XML document has a single root, so the models are wrapped
+        // into a synthetic one
+        let text = format!(
+            "<{0}>{1}</{0}>",
+            MULTI_MODEL_ROOT,
+            strip_xml_declarations(text)
+        );
+        let doc = roxmltree::Document::parse(&text)?;
+
+        doc.root_element()
+            .children()
+            .filter(|child| child.is_element())
+            .map(|root| Self::parse_root(&logger, ParseOptions::default(), &root))
+            .collect()
+    }
+
+    fn parse(
+        text: &str,
+        logger: &slog::Logger,
+        parse_options: ParseOptions,
+    ) -> MJCFParseResult<MJCFModel<N>> {
+        let doc = roxmltree::Document::parse(text)?;
+        Self::parse_root(logger, parse_options, &doc.root_element())
+    }
+
+    fn parse_root(
+        logger: &slog::Logger,
+        parse_options: ParseOptions,
+        root: &roxmltree::Node,
+    ) -> MJCFParseResult<MJCFModel<N>> {
+        let mut mjcf_model = MJCFModel::empty(parse_options);
+
+        if !root.has_tag_name("mujoco") {
+            return Err(
+                MJCFParseErrorKind::NotMuJoCoModel(root.tag_name().name().to_string()).into(),
+            );
+        }
+        mjcf_model.reserve(root);
+        if let Some(model_name) = root.attribute("model") {
+            mjcf_model.model_name = model_name.to_string();
+        }
+
+        // The compiler settings affect how every other element is
+        // interpreted, and assets are referenced by geoms, so they must
+        // be parsed first regardless of where they appear in the
+        // document.
+        for child in root.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "compiler" => mjcf_model.compiler = compiler::parse_compiler_node(logger, &child)?,
+                "option" => {
+                    mjcf_model.options =
+                        option::parse_option_node(logger, &mjcf_model.parse_options, &child)?
+                }
+                "asset" => mjcf_model.parse_asset(logger, &child)?,
+                "default" => default::parse_default_node(
+                    logger,
+                    &mjcf_model.parse_options,
+                    &child,
+                    &mut mjcf_model.defaults,
+                )?,
+                _ => {}
+            }
+        }
+
+        for child in root.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "compiler" | "option" | "asset" | "default" => {}
+                "worldbody" => mjcf_model.parse_worldbody(logger, &child)?,
+                _ if mjcf_model.parse_options.strict_unknown_elements => {
+                    return Err(
+                        MJCFParseErrorKind::UnknownElement(tags::element_path(&child)).into(),
+                    );
+                }
+                tag_name => {
+                    warn!(logger, "Unsupported element. Ignoring"; "element" => tag_name);
+                    mjcf_model.record_unsupported_element(&child);
+                }
+            };
+        }
+
+        if let Some(total_mass) = mjcf_model.compiler.settotalmass {
+            mjcf_model.scale_to_total_mass(total_mass);
+        }
+        mjcf_model.tree = mjcf_model.body_node(WORLDBODY_NAME);
+
+        Ok(mjcf_model)
+    }
+
+    /// Reserve room for the geoms and bodies of the document, so that
+    /// large models are not rehashed while they are parsed.
+    fn reserve(&mut self, root: &roxmltree::Node) {
+        let mut num_geoms = 0;
+        let mut num_bodies = 0;
+        for worldbody in root
+            .children()
+            .filter(|child| child.has_tag_name("worldbody"))
+        {
+            for node in worldbody.descendants() {
+                if node.has_tag_name("geom") {
+                    num_geoms += 1;
+                } else if node.has_tag_name("body") {
+                    num_bodies += 1;
+                }
+            }
+        }
+
+        self.colliders.reserve(num_geoms);
+        self.geom_bodies.reserve(num_geoms);
+        self.shape_kinds.reserve(num_geoms);
+        self.bodies.reserve(num_bodies);
+    }
+
+    /// A copy of the model where every geom, site and light is a direct
+    /// child of the world body at its world position.
+    ///
+    /// All other bodies and all joints are dropped, so the result is only
+    /// useful for static scenes or engines without articulations. User
+    /// data cannot be cloned, so it is not copied either.
+    pub fn flatten(&self) -> MJCFModel<N> {
+        let mut flat_model = MJCFModel::empty(self.parse_options.clone());
+        flat_model.model_name = self.model_name.clone();
+        flat_model.compiler = self.compiler.clone();
+        flat_model.options = self.options.clone();
+        flat_model.defaults = self.defaults.clone();
+        flat_model.materials = self.materials.clone();
+        flat_model.material_descs = self.material_descs.clone();
+        flat_model.textures = self.textures.clone();
+        flat_model.num_textures = self.num_textures;
+        flat_model.meshes = self.meshes.clone();
+        flat_model.referenced_assets = self.referenced_assets.clone();
+        flat_model.discarded_geoms = self.discarded_geoms.clone();
+        flat_model.shape_kinds = self.shape_kinds.clone();
+        flat_model.num_geoms = self.num_geoms;
+        flat_model.num_sites = self.num_sites;
+        flat_model.num_lights = self.num_lights;
+        flat_model.lights = self.lights.clone();
+        flat_model.light_bodies = self
+            .light_bodies
+            .keys()
+            .map(|name| (name.clone(), WORLDBODY_NAME.to_string()))
+            .collect();
+        flat_model.diagnostics = self.diagnostics.clone();
+
+        for (name, collider) in self.iter_geoms() {
+            let position = self
+                .geom_world_position(name)
+                .expect("Geom body must be in the model");
+            flat_model
+                .colliders
+                .insert(name.to_string(), collider.clone().position(position));
+            flat_model
+                .geom_bodies
+                .insert(name.to_string(), WORLDBODY_NAME.to_string());
+            if let Some(world) = flat_model.bodies.get_mut(WORLDBODY_NAME) {
+                world.geoms.push(name.to_string());
+            }
+        }
+
+        for body in self.iter_bodies() {
+            let body_position = self
+                .body_world_position(&body.name)
+                .expect("Body ancestors must be in the model");
+            for name in &body.sites {
+                let mut site = self.sites[name].clone();
+                site.position = body_position * site.position;
+                flat_model.sites.insert(name.clone(), site);
+                flat_model
+                    .site_bodies
+                    .insert(name.clone(), WORLDBODY_NAME.to_string());
+                if let Some(world) = flat_model.bodies.get_mut(WORLDBODY_NAME) {
+                    world.sites.push(name.clone());
+                }
+            }
+        }
+
+        for (name, light) in &self.body_lights {
+            let body_position = self
+                .body_world_position(&self.light_bodies[name])
+                .expect("Light body must be in the model");
+            let mut light = light.clone();
+            light.position = body_position * light.position;
+            light.direction = body_position.rotation * light.direction;
+            flat_model.lights.insert(name.clone(), light);
+        }
+        flat_model.tree = flat_model.body_node(WORLDBODY_NAME);
+
+        flat_model
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    pub fn parse_options(&self) -> &ParseOptions {
+        &self.parse_options
+    }
+
+    pub fn compiler(&self) -> &CompilerOptions<N> {
+        &self.compiler
+    }
+
+    /// Compiler settings indicating the MuJoCo version the model was
+    /// written for.
+    pub fn meta(&self) -> ModelMeta {
+        self.compiler.meta()
+    }
+
+    pub fn options(&self) -> &OptionConfig<N> {
+        &self.options
+    }
+
+    pub fn gravity_vector(&self) -> Vector3<N> {
+        self.options.gravity
+    }
+
+    pub fn gravity_magnitude(&self) -> N {
+        self.options.gravity.norm()
+    }
+
+    /// Direction gravity pulls in, or `None` in zero gravity.
+    pub fn gravity_direction(&self) -> Option<Unit<Vector3<N>>> {
+        Unit::try_new(
+            self.options.gravity,
+            self.parse_options.degenerate_epsilon(),
+        )
+    }
+
+    /// Problems that were skipped over because the model was not parsed
+    /// in strict mode.
+    pub fn diagnostics(&self) -> &ParseDiagnostics {
+        &self.diagnostics
+    }
+
+    /// Attach application specific data to the model, e.g. the path it
+    /// was loaded from. Replaces any previous user data.
+    pub fn set_user_data<T: Any + Send + Sync>(&mut self, user_data: T) {
+        self.user_data = Some(Box::new(user_data));
+    }
+
+    /// User data of the model, if there is any and it has type `T`.
+    pub fn user_data_ref<T: Any>(&self) -> Option<&T> {
+        self.user_data
+            .as_ref()
+            .and_then(|user_data| user_data.downcast_ref())
+    }
+
+    pub fn colliders(&self) -> &HashMap<String, ColliderDesc<N>> {
+        &self.colliders
+    }
+
+    pub fn get_collider(&self, name: &str) -> Option<&ColliderDesc<N>> {
+        self.colliders.get(name)
+    }
+
+    /// Geoms in document order.
+    pub fn iter_geoms(&self) -> impl Iterator<Item = (&str, &ColliderDesc<N>)> {
+        let mut geoms: Vec<_> = self
+            .colliders
+            .iter()
+            .map(|(name, collider)| (name.as_str(), collider))
+            .collect();
+        geoms.sort_by_key(|(_, collider)| {
+            GeomUserData::from_collider_desc(collider).map(|user_data| user_data.order)
+        });
+        geoms.into_iter()
+    }
+
+    pub fn materials(&self) -> &HashMap<String, MaterialHandle<N>> {
+        &self.materials
+    }
+
+    pub fn get_material(&self, name: &str) -> Option<&MaterialHandle<N>> {
+        self.materials.get(name)
+    }
+
+    pub fn textures(&self) -> &HashMap<String, TextureAsset<N>> {
+        &self.textures
+    }
+
+    pub fn get_texture(&self, name: &str) -> Option<&TextureAsset<N>> {
+        self.textures.get(name)
+    }
+
+    pub fn meshes(&self) -> &HashMap<String, MeshAsset<N>> {
+        &self.meshes
+    }
+
+    pub fn get_mesh(&self, name: &str) -> Option<&MeshAsset<N>> {
+        self.meshes.get(name)
+    }
+
+    /// All bodies in the model, including the world body.
+    pub fn bodies(&self) -> &HashMap<String, BodyDesc<N>> {
+        &self.bodies
+    }
+
+    /// Names of all meshes, textures, materials and height fields
+    /// referenced by geoms and materials, including geoms that were
+    /// skipped. Comparing them against the defined assets finds dangling
+    /// references.
+    pub fn referenced_assets(&self) -> HashSet<String> {
+        self.referenced_assets.clone()
+    }
+
+    /// Bodies in document order, starting with the world body.
+    pub fn iter_bodies(&self) -> impl Iterator<Item = &BodyDesc<N>> {
+        let mut bodies: Vec<_> = self.bodies.values().collect();
+        bodies.sort_by_key(|body| body.order);
+        bodies.into_iter()
+    }
+
+    /// Bodies level by level, starting with the world body. Children of a
+    /// body are in document order.
+    pub fn bodies_bfs(&self) -> Vec<&BodyDesc<N>> {
+        let mut bodies = vec![&self.bodies[WORLDBODY_NAME]];
+        let mut next = 0;
+        while next < bodies.len() {
+            let body = bodies[next];
+            bodies.extend(body.children.iter().map(|child| &self.bodies[child]));
+            next += 1;
+        }
+        bodies
+    }
+
+    pub fn get_body(&self, name: &str) -> Option<&BodyDesc<N>> {
+        self.bodies.get(name)
+    }
+
+    /// Name of the body the geom is attached to.
+    pub fn geom_body(&self, geom_name: &str) -> Option<&str> {
+        self.geom_bodies.get(geom_name).map(String::as_str)
+    }
+
+    /// Kind of the geom's collision shape, so that it does not have to be
+    /// downcast to find out.
+    pub fn geom_shape_kind(&self, geom_name: &str) -> Option<ShapeKind> {
+        self.shape_kinds.get(geom_name).cloned()
+    }
+
+    /// Swap the collision shape of a geom, keeping its position, density
+    /// and geom properties. An ellipsoid geom becomes a geom of the new
+    /// shape's kind.
+    pub fn replace_geom_shape(
+        &mut self,
+        name: &str,
+        shape: ShapeHandle<N>,
+    ) -> Result<(), UnknownGeomError> {
+        let collider = self
+            .colliders
+            .get_mut(name)
+            .ok_or_else(|| UnknownGeomError(name.to_string()))?;
+        match ShapeKind::of(&shape) {
+            Some(shape_kind) => self.shape_kinds.insert(name.to_string(), shape_kind),
+            None => self.shape_kinds.remove(name),
+        };
+        // The new shape no longer approximates an ellipsoid
+        let user_data = GeomUserData::from_collider_desc(collider).map(|user_data| GeomUserData {
+            semi_axes: None,
+            ..user_data.clone()
+        });
+        collider.set_shape(shape);
+        if let Some(user_data) = user_data {
+            *collider = collider.clone().user_data(user_data);
+        }
+        Ok(())
+    }
+
+    pub fn joints(&self) -> &HashMap<String, JointDesc<N>> {
+        &self.joints
+    }
+
+    pub fn get_joint(&self, name: &str) -> Option<&JointDesc<N>> {
+        self.joints.get(name)
+    }
+
+    /// Name of the body the joint belongs to.
+    pub fn joint_body(&self, joint_name: &str) -> Option<&str> {
+        self.joint_bodies.get(joint_name).map(String::as_str)
+    }
+
+    pub fn sites(&self) -> &HashMap<String, SiteDesc<N>> {
+        &self.sites
+    }
+
+    pub fn get_site(&self, name: &str) -> Option<&SiteDesc<N>> {
+        self.sites.get(name)
+    }
+
+    /// Name of the body the site is attached to.
+    pub fn site_body(&self, site_name: &str) -> Option<&str> {
+        self.site_bodies.get(site_name).map(String::as_str)
+    }
+
+    /// Static scene lights, i.e. the lights of the world body, with
+    /// positions and directions in the world frame.
+    pub fn lights(&self) -> &HashMap<String, LightDesc<N>> {
+        &self.lights
+    }
+
+    /// Lights attached to bodies other than the world body. Their
+    /// positions and directions are relative to their body's frame.
+    pub fn body_lights(&self) -> &HashMap<String, LightDesc<N>> {
+        &self.body_lights
+    }
+
+    /// Name of the body the light is attached to.
+    pub fn light_body(&self, light_name: &str) -> Option<&str> {
+        self.light_bodies.get(light_name).map(String::as_str)
+    }
+
+    /// The world body with all of its descendants.
+    pub fn tree(&self) -> &BodyNode<N> {
+        &self.tree
+    }
+
+    fn body_node(&self, name: &str) -> BodyNode<N> {
+        let body = &self.bodies[name];
+        BodyNode {
+            name: body.name.clone(),
+            position: body.position,
+            geoms: body.geoms.clone(),
+            sites: body.sites.clone(),
+            joints: body.joints.clone(),
+            children: body
+                .children
+                .iter()
+                .map(|child| self.body_node(child))
+                .collect(),
+        }
+    }
+
+    /// Byte range of the body's element in the text the model was parsed
+    /// from, e.g. to highlight it in an editor. The ranges of models
+    /// returned by `parse_multi` do not refer to the original text.
+    pub fn body_source_range(&self, name: &str) -> Option<Range<usize>> {
+        self.bodies.get(name)?.source_range.clone()
+    }
+
+    /// Position of the body in the world frame, composed from the
+    /// positions of all of its ancestors.
+    pub fn body_world_position(&self, name: &str) -> Option<Isometry3<N>> {
+        let body = self.bodies.get(name)?;
+        match &body.parent {
+            Some(parent) => Some(self.body_world_position(parent)? * body.position),
+            None => Some(body.position),
+        }
+    }
+
+    /// Position of the geom's collider in the world frame.
+    pub fn geom_world_position(&self, name: &str) -> Option<Isometry3<N>> {
+        let collider = self.colliders.get(name)?;
+        let body_position = self.body_world_position(self.geom_body(name)?)?;
+        Some(body_position * collider.get_position())
+    }
+
+    /// Scale the densities of all geoms outside of the world body so that
+    /// the bodies add up to `total_mass`.
+    ///
+    /// Masses raised by `boundmass` do not scale with the densities, so
+    /// the result only matches exactly when no body is clamped.
+    fn scale_to_total_mass(&mut self, total_mass: N) {
+        let current_total_mass = self.total_mass();
+        if current_total_mass <= N::zero() {
+            return;
+        }
+        let scale = total_mass / current_total_mass;
+
+        for (name, collider) in self.colliders.iter_mut() {
+            if self.geom_bodies[name] != WORLDBODY_NAME {
+                *collider = collider.clone().density(collider.get_density() * scale);
+            }
+        }
+    }
+
+    fn parse_asset(
+        &mut self,
+        logger: &slog::Logger,
+        asset_node: &roxmltree::Node,
+    ) -> MJCFParseResult<()> {
+        for child in asset_node.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "material" => {
+                    if let Some(texture) = child.attribute("texture") {
+                        self.referenced_assets.insert(texture.to_string());
+                    }
+                    let (name, material) = material::parse_material_node(logger, &child)?;
+                    let name = self.normalize_name(name);
+                    if self.material_descs.contains_key(&name) {
+                        return Err(MJCFParseErrorKind::DuplicateMaterialName(name).into());
+                    }
+                    // MuJoCo materials only describe the appearance, so
+                    // they all share MuJoCo's default sliding friction
+                    self.materials.insert(
+                        name.clone(),
+                        MaterialHandle::new(BasicMaterial::new(N::zero(), N::one())),
+                    );
+                    self.material_descs.insert(name, material);
+                }
+                "texture" => {
+                    let (name, texture) = texture::parse_texture_node(logger, &child)?;
+                    // Unnamed textures are named by their order in the
+                    // document
+                    let name = name.unwrap_or_else(|| format!("texture{}", self.num_textures));
+                    self.num_textures += 1;
+                    if self.textures.contains_key(&name) {
+                        return Err(MJCFParseErrorKind::DuplicateTextureName(name).into());
+                    }
+                    self.textures.insert(name, texture);
+                }
+                "mesh" => {
+                    let (name, mesh) = mesh::parse_mesh_node(logger, &self.parse_options, &child)?;
+                    if self.meshes.contains_key(&name) {
+                        return Err(MJCFParseErrorKind::DuplicateMeshName(name).into());
+                    }
+                    self.meshes.insert(name, mesh);
+                }
+                _ if self.parse_options.strict_unknown_elements => {
+                    return Err(
+                        MJCFParseErrorKind::UnknownElement(tags::element_path(&child)).into(),
+                    );
+                }
+                tag_name => {
+                    warn!(logger, "Unsupported asset element. Ignoring"; "element" => tag_name);
+                    self.record_unsupported_element(&child);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Anchor of the joint in the world frame.
+    pub fn joint_world_anchor(&self, name: &str) -> Option<Point3<N>> {
+        let joint = self.joints.get(name)?;
+        let body_position = self.body_world_position(self.joint_body(name)?)?;
+        Some(body_position * joint.pos)
+    }
+
+    fn parse_worldbody(
+        &mut self,
+        logger: &slog::Logger,
+        worldbody_node: &roxmltree::Node,
+    ) -> MJCFParseResult<()> {
+        self.parse_body_children(logger, worldbody_node, WORLDBODY_NAME)
+    }
+
+    fn parse_body_children(
+        &mut self,
+        logger: &slog::Logger,
+        body_node: &roxmltree::Node,
+        body_name: &str,
+    ) -> MJCFParseResult<()> {
+        for child in body_node.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "geom" => {
+                    if body_name == WORLDBODY_NAME {
+                        warn_static_geom_attributes(logger, &child);
+                    }
+                    match self.parse_geom(logger, &child, body_name) {
+                        // Outside of strict mode a malformed geom is left
+                        // out instead of discarding the whole model
+                        Err(MJCFParseError {
+                            kind: MJCFParseErrorKind::Geom(ref error),
+                        }) if !self.parse_options.strict => {
+                            warn!(logger, "Malformed geom. Skipping"; "error" => error.to_string());
+                            self.diagnostics.push(Diagnostic {
+                                kind: DiagnosticKind::SkippedGeom,
+                                element: child.attribute("name").map(str::to_string),
+                                message: error.to_string(),
+                                source_range: Some(child.range()),
+                            });
+                        }
+                        result => result?,
+                    }
+                }
+                "body" => self.parse_body(logger, &child, body_name)?,
+                "site" => self.parse_site(logger, &child, body_name)?,
+                "light" => self.parse_light(logger, &child, body_name)?,
+                // The world body is static, so it cannot contain joints
+                "joint" if body_name != WORLDBODY_NAME => {
+                    self.parse_joint(logger, &child, body_name)?
+                }
+                _ if self.parse_options.strict_unknown_elements => {
+                    return Err(
+                        MJCFParseErrorKind::UnknownElement(tags::element_path(&child)).into(),
+                    );
+                }
+                tag_name @ "joint" | tag_name @ "freejoint" | tag_name @ "inertial"
+                    if body_name == WORLDBODY_NAME =>
+                {
+                    warn!(logger, "The world body is static, so it cannot move or have inertia. Ignoring";
+                          "element" => tag_name);
+                    self.record_unsupported_element(&child);
+                }
+                tag_name => {
+                    warn!(logger, "Unsupported body element. Ignoring"; "element" => tag_name);
+                    self.record_unsupported_element(&child);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_body(
+        &mut self,
+        logger: &slog::Logger,
+        body_node: &roxmltree::Node,
+        parent_name: &str,
+    ) -> MJCFParseResult<()> {
+        // Unnamed bodies are named by their order in the document
+        let name = match body_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("body{}", self.num_bodies),
+        };
+        let name = self.normalize_name(name);
+        self.num_bodies += 1;
+        self.record_unsupported_attributes(body_node, &name, body::SUPPORTED_ATTRIBUTES);
+        if self.bodies.contains_key(&name) {
+            return Err(MJCFParseErrorKind::DuplicateBodyName(name).into());
+        }
+
+        let logger = logger.new(o!("body" => name.clone()));
+        let order = self.bodies.len();
+        let mut body = body::parse_body_node(
+            &logger,
+            &self.parse_options,
+            &self.compiler,
+            body_node,
+            name.clone(),
+            parent_name,
+            order,
+        )
+        .map_err(|error| MJCFParseErrorKind::Body {
+            body: name.clone(),
+            error,
+        })?;
+        body.doc = self.parse_doc(body_node);
+        body.source_range = Some(body_node.range());
+        self.bodies.insert(name.clone(), body);
+        if let Some(parent) = self.bodies.get_mut(parent_name) {
+            parent.children.push(name.clone());
+        }
+
+        if let Some(childclass) = body_node.attribute("childclass") {
+            if self.defaults.get_class(childclass).is_none() {
+                return Err(MJCFParseErrorKind::UnknownClass(childclass.to_string()).into());
+            }
+            self.childclass_stack.push(childclass.to_string());
+        }
+        self.parse_body_children(&logger, body_node, &name)?;
+        if body_node.has_attribute("childclass") {
+            self.childclass_stack.pop();
+        }
+
+        // TODO(dschwab): MuJoCo composes multiple joints of a body. Only
+        // the first one is used when building.
+        if self.bodies[&name].joints.len() > 1 {
+            warn!(
+                logger,
+                "Multiple joints per body are not supported yet. Only the first joint is used"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Documentation of a named element, taken from the comment preceding
+    /// it.
+    fn parse_doc(&self, node: &roxmltree::Node) -> Option<String> {
+        if self.parse_options.capture_comments && node.has_attribute("name") {
+            tags::preceding_comment(node)
+        } else {
+            None
+        }
+    }
+
+    /// Record every attribute of `node` the parser ignores. The element
+    /// parsers log them as well.
+    fn record_unsupported_attributes(
+        &mut self,
+        node: &roxmltree::Node,
+        name: &str,
+        supported_attributes: &[&str],
+    ) {
+        self.record_unused_attributes(node, name, supported_attributes, false);
+    }
+
+    /// Record every attribute of `node` the parser ignores, like
+    /// `record_unsupported_attributes`, but count attributes matched
+    /// with `ignore_case` as used.
+    fn record_unused_attributes(
+        &mut self,
+        node: &roxmltree::Node,
+        name: &str,
+        supported_attributes: &[&str],
+        ignore_case: bool,
+    ) {
+        for attribute in tags::unused_attributes(node, supported_attributes, ignore_case) {
+            self.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnsupportedAttribute,
+                element: Some(name.to_string()),
+                message: format!(
+                    "unsupported {} attribute '{}'",
+                    node.tag_name().name(),
+                    attribute
+                ),
+                source_range: Some(node.range()),
+            });
+        }
+    }
+
+    /// Record an element the parser ignores, named by its path in the
+    /// document.
+    fn record_unsupported_element(&mut self, node: &roxmltree::Node) {
+        self.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::UnsupportedElement,
+            element: node.attribute("name").map(str::to_string),
+            message: format!("unsupported element '{}'", tags::element_path(node)),
+            source_range: Some(node.range()),
+        });
+    }
+
+    /// Lowercases a geom, body or material name when
+    /// `ParseOptions::lowercase_names` is set.
+    fn normalize_name(&self, name: String) -> String {
+        if self.parse_options.lowercase_names {
+            name.to_lowercase()
+        } else {
+            name
+        }
+    }
+
+    fn parse_joint(
+        &mut self,
+        logger: &slog::Logger,
+        joint_node: &roxmltree::Node,
+        body_name: &str,
+    ) -> MJCFParseResult<()> {
+        // Unnamed joints are named by their order in the document
+        let name = match joint_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("joint{}", self.num_joints),
+        };
+        self.num_joints += 1;
+        self.record_unsupported_attributes(joint_node, &name, joint::SUPPORTED_ATTRIBUTES);
+        if self.joints.contains_key(&name) {
+            return Err(MJCFParseErrorKind::DuplicateJointName(name).into());
+        }
+
+        let logger = logger.new(o!("joint" => name.clone()));
+        let joint =
+            joint::parse_joint_node(&logger, &self.parse_options, &self.compiler, joint_node)?;
+
+        self.joints.insert(name.clone(), joint);
+        if let Some(body) = self.bodies.get_mut(body_name) {
+            body.joints.push(name.clone());
+        }
+        self.joint_bodies.insert(name, body_name.to_string());
+
+        Ok(())
+    }
+
+    fn parse_site(
+        &mut self,
+        logger: &slog::Logger,
+        site_node: &roxmltree::Node,
+        body_name: &str,
+    ) -> MJCFParseResult<()> {
+        // Unnamed sites are named by their order in the document
+        let name = match site_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("site{}", self.num_sites),
+        };
+        self.num_sites += 1;
+        self.record_unsupported_attributes(site_node, &name, site::SUPPORTED_ATTRIBUTES);
+        if self.sites.contains_key(&name) {
+            return Err(MJCFParseErrorKind::DuplicateSiteName(name).into());
+        }
+
+        let logger = logger.new(o!("site" => name.clone()));
+        let site = site::parse_site_node(&logger, &self.parse_options, &self.compiler, site_node)?;
+
+        self.sites.insert(name.clone(), site);
+        if let Some(body) = self.bodies.get_mut(body_name) {
+            body.sites.push(name.clone());
+        }
+        self.site_bodies.insert(name, body_name.to_string());
+
+        Ok(())
+    }
+
+    fn parse_light(
+        &mut self,
+        logger: &slog::Logger,
+        light_node: &roxmltree::Node,
+        body_name: &str,
+    ) -> MJCFParseResult<()> {
+        // Unnamed lights are named by their order in the document
+        let name = match light_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("light{}", self.num_lights),
+        };
+        self.num_lights += 1;
+        self.record_unsupported_attributes(light_node, &name, light::SUPPORTED_ATTRIBUTES);
+        if self.lights.contains_key(&name) || self.body_lights.contains_key(&name) {
+            return Err(MJCFParseErrorKind::DuplicateLightName(name).into());
+        }
+
+        let logger = logger.new(o!("light" => name.clone()));
+        let light = light::parse_light_node(&logger, &self.parse_options, light_node)?;
+
+        // The world body never moves, so its frame is the world frame
+        if body_name == WORLDBODY_NAME {
+            self.lights.insert(name.clone(), light);
+        } else {
+            self.body_lights.insert(name.clone(), light);
+        }
+        self.light_bodies.insert(name, body_name.to_string());
+
+        Ok(())
+    }
+
+    fn parse_geom(
+        &mut self,
+        logger: &slog::Logger,
+        geom_node: &roxmltree::Node,
+        body_name: &str,
+    ) -> MJCFParseResult<()> {
+        // Unnamed geoms are named by their order in the document
+        let order = self.num_geoms;
+        let name = match geom_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("geom{}", order),
+        };
+        let name = if self.parse_options.scoped_geom_names {
+            format!("{}/{}", body_name, name)
+        } else {
+            name
+        };
+        let name = self.normalize_name(name);
+        self.num_geoms += 1;
+        // Outside of strict mode geoms use attributes with the wrong case
+        self.record_unused_attributes(
+            geom_node,
+            &name,
+            geom::SUPPORTED_ATTRIBUTES,
+            !self.parse_options.strict,
+        );
+
+        let logger = logger.new(o!("geom" => name.clone()));
+        // An explicit class takes precedence over the childclass of the
+        // enclosing bodies
+        let class = geom_node
+            .attribute("class")
+            .or_else(|| self.childclass_stack.last().map(String::as_str))
+            .unwrap_or(MAIN_CLASS);
+        let defaults = self
+            .defaults
+            .geom_attributes(class)
+            .ok_or_else(|| MJCFParseErrorKind::UnknownClass(class.to_string()))?;
+        for attribute in &["mesh", "material", "hfield"] {
+            let asset = geom_node
+                .attribute(*attribute)
+                .or_else(|| defaults.get(*attribute).map(String::as_str));
+            if let Some(asset) = asset {
+                self.referenced_assets.insert(asset.to_string());
+            }
+        }
+        // Like every other attribute, the material and rgba may come from
+        // the default class
+        let material_name = geom_node
+            .attribute("material")
+            .or_else(|| defaults.get("material").map(String::as_str))
+            .map(str::to_string);
+        let has_rgba = geom_node.has_attribute("rgba") || defaults.contains_key("rgba");
+        let collider = geom::parse_geom_node_with_defaults(
+            &logger,
+            &self.parse_options,
+            &self.compiler,
+            geom_node,
+            defaults,
+            &self.meshes,
+        )?;
+        let mut user_data = GeomUserData::from_collider_desc(&collider)
+            .cloned()
+            .unwrap_or_default();
+        user_data.order = order;
+        user_data.doc = self.parse_doc(geom_node);
+        // A geom rgba takes precedence over the material's color
+        if let Some(material_name) = material_name {
+            let material_key = self.normalize_name(material_name.clone());
+            let material = self.material_descs.get(&material_key).ok_or_else(|| {
+                MJCFParseErrorKind::UnknownMaterial {
+                    geom: name.clone(),
+                    material: material_name,
+                }
+            })?;
+            if !has_rgba {
+                user_data.rgba = material.rgba;
+                user_data.transparent = material.rgba.w < N::one();
+            }
+        }
+        let is_visual = !user_data.is_collidable();
+        let collider = collider.user_data(user_data);
+
+        if self.colliders.contains_key(&name) || self.discarded_geoms.contains(&name) {
+            return Err(MJCFParseErrorKind::DuplicateGeomName(name).into());
+        }
+        // The geom is still counted above, so that discarding it does not
+        // change the names of the unnamed geoms after it
+        if is_visual && self.compiler.discardvisual {
+            self.discarded_geoms.insert(name);
+            return Ok(());
+        }
+        if let Some(shape_kind) = ShapeKind::of_collider(&collider) {
+            self.shape_kinds.insert(name.clone(), shape_kind);
+        }
+        self.colliders.insert(name.clone(), collider);
+        if let Some(body) = self.bodies.get_mut(body_name) {
+            body.geoms.push(name.clone());
+        }
+        self.geom_bodies.insert(name, body_name.to_string());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use na::Point4;
+    use ncollide3d::shape::{Ball, Cuboid};
+    use std::sync::{Arc, Mutex};
+
+    struct MemoryDrain {
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl slog::Drain for MemoryDrain {
+        type Ok = ();
+        type Err = slog::Never;
+
+        fn log(
+            &self,
+            record: &slog::Record,
+            _values: &slog::OwnedKVList,
+        ) -> Result<Self::Ok, Self::Err> {
+            self.messages
+                .lock()
+                .unwrap()
+                .push(format!("{}", record.msg()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn parse_model_name_and_options() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco model="test"><option gravity="0 0 -1"/></mujoco>"#,
+        )
+        .unwrap();
+        assert_eq!(model.model_name(), "test");
+        assert_eq!(model.options().gravity, Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn expose_compiler_meta() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <compiler coordinate="global" angle="radian"/>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let meta = model.meta();
+        assert_eq!(meta.coordinate, compiler::Coordinate::Global);
+        assert_eq!(meta.angle, compiler::AngleUnit::Radian);
+        assert_eq!(meta.autolimits, None);
+    }
+
+    #[test]
+    fn collect_referenced_assets() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <asset>
+                   <material name="steel" texture="brushed"/>
+                 </asset>
+                 <worldbody>
+                   <geom name="part" type="sphere" size="0.1" mesh="bracket" material="steel"/>
+                   <geom mesh="missing"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let assets = model.referenced_assets();
+        assert!(assets.contains("bracket"));
+        assert!(assets.contains("steel"));
+        assert!(assets.contains("brushed"));
+        // Referenced by a mesh geom, which is skipped as unsupported
+        assert!(assets.contains("missing"));
+        assert_eq!(assets.len(), 4);
+    }
+
+    #[test]
+    fn fit_sphere_to_mesh() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <compiler fitscale="2"/>
+                 <asset>
+                   <mesh name="tetrahedron" vertex="1 0 0  0 2 0  0 0 -1  -1 -1 1"/>
+                 </asset>
+                 <worldbody>
+                   <geom name="fitted" type="sphere" mesh="tetrahedron"/>
+                   <geom name="sized" type="sphere" size="0.1" mesh="missing"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        // The vertex farthest from the mesh origin is (0, 2, 0)
+        let fitted = model.get_collider("fitted").unwrap();
+        assert_relative_eq!(
+            fitted.get_shape().as_shape::<Ball<f64>>().unwrap().radius(),
+            4.0
+        );
+        let sized = model.get_collider("sized").unwrap();
+        assert_relative_eq!(
+            sized.get_shape().as_shape::<Ball<f64>>().unwrap().radius(),
+            0.1
+        );
+    }
+
+    #[test]
+    fn default_gravity() {
+        let model = MJCFModel::<f64>::parse_xml_string("<mujoco/>").unwrap();
+        assert_relative_eq!(model.gravity_magnitude(), 9.81);
+        assert_eq!(
+            model.gravity_direction(),
+            Some(Unit::new_normalize(Vector3::new(0.0, 0.0, -1.0)))
+        );
+
+        let model =
+            MJCFModel::<f64>::parse_xml_string(r#"<mujoco><option gravity="0 0 0"/></mujoco>"#)
+                .unwrap();
+        assert_eq!(model.gravity_vector(), Vector3::zeros());
+        assert_eq!(model.gravity_direction(), None);
+    }
+
+    #[test]
+    fn parse_comma_separated_gravity() {
+        let text = r#"<mujoco><option gravity="0,0,-9.81"/></mujoco>"#;
+        let model = MJCFModel::<f64>::parse_xml_string(text).unwrap();
+        assert_eq!(model.gravity_vector(), Vector3::new(0.0, 0.0, -9.81));
+
+        let strict = ParseOptions::builder().strict(true).build();
+        let error = MJCFModel::<f64>::parse_xml_string_with_options(text, &strict).unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::Option(_) => {}
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_concatenated_models() {
+        let models = MJCFModel::<f64>::parse_multi(
+            r#"<?xml version="1.0"?>
+               <mujoco model="first">
+                 <worldbody><geom name="ball" size="0.5"/></worldbody>
+               </mujoco>
+               <?xml version="1.0"?>
+               <mujoco model="second">
+                 <worldbody><geom name="ball" type="box" size="1 1 1"/></worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].model_name(), "first");
+        assert_eq!(models[1].model_name(), "second");
+        assert_eq!(models[0].geom_shape_kind("ball"), Some(ShapeKind::Ball));
+        assert_eq!(models[1].geom_shape_kind("ball"), Some(ShapeKind::Box));
+    }
+
+    #[test]
+    fn reject_non_mujoco_root() {
+        let error = MJCFModel::<f64>::parse_xml_string("<robot/>").unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::NotMuJoCoModel(tag_name) => assert_eq!(tag_name, "robot"),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_worldbody_geoms() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <geom type="sphere" size="0.5"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+        assert_eq!(model.colliders().len(), 2);
+        assert!(model.get_collider("floor").is_some());
+        assert!(model.get_collider("geom1").is_some());
+    }
+
+    #[test]
+    fn parse_nested_bodies() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="torso" pos="0 0 1">
+                     <geom name="torso_geom" size="0.1"/>
+                     <body name="arm" pos="1 0 0">
+                       <geom name="hand" size="0.1" pos="0 1 0"/>
+                     </body>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.bodies().len(), 3);
+        assert_eq!(
+            model.get_body(WORLDBODY_NAME).unwrap().children,
+            vec![String::from("torso")]
+        );
+        let arm = model.get_body("arm").unwrap();
+        assert_eq!(arm.parent, Some(String::from("torso")));
+        assert_eq!(arm.geoms, vec![String::from("hand")]);
+        assert_eq!(model.geom_body("hand"), Some("arm"));
+        assert_eq!(
+            model
+                .geom_world_position("hand")
+                .unwrap()
+                .translation
+                .vector,
+            Vector3::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn iterate_in_document_order() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="zebra" size="0.1"/>
+                   <body name="yak">
+                     <geom name="aardvark" size="0.1"/>
+                   </body>
+                   <body name="bison"/>
+                   <geom name="mole" size="0.1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let geom_names: Vec<_> = model.iter_geoms().map(|(name, _)| name).collect();
+        assert_eq!(geom_names, vec!["zebra", "aardvark", "mole"]);
+        let body_names: Vec<_> = model.iter_bodies().map(|body| body.name.as_str()).collect();
+        assert_eq!(body_names, vec![WORLDBODY_NAME, "yak", "bison"]);
+    }
+
+    #[test]
+    fn keep_worldbody_geoms_and_sites() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <site name="origin" pos="0 0 0.1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.colliders().len(), 1);
+        assert_eq!(model.sites().len(), 1);
+        assert_eq!(model.site_body("origin"), Some(WORLDBODY_NAME));
+        assert_eq!(
+            model.get_body(WORLDBODY_NAME).unwrap().sites,
+            vec![String::from("origin")]
+        );
+    }
+
+    #[test]
+    fn store_worldbody_lights_as_scene_lights() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <light name="sun" pos="1 2 3" dir="0 0 -1" directional="true"/>
+                   <body name="robot" pos="0 0 1">
+                     <light name="headlamp" pos="0.1 0 0" dir="1 0 0"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.lights().len(), 1);
+        let sun = &model.lights()["sun"];
+        assert_eq!(sun.position, Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(sun.direction.into_inner(), Vector3::new(0.0, 0.0, -1.0));
+        assert!(sun.directional);
+        assert_eq!(model.light_body("sun"), Some(WORLDBODY_NAME));
+
+        // Body lights are kept in their body's frame
+        assert_eq!(
+            model.body_lights()["headlamp"].position,
+            Point3::new(0.1, 0.0, 0.0)
+        );
+        assert_eq!(model.light_body("headlamp"), Some("robot"));
+
+        let flat_model = model.flatten();
+        assert_eq!(
+            flat_model.lights()["headlamp"].position,
+            Point3::new(0.1, 0.0, 1.0)
+        );
+        assert!(flat_model.body_lights().is_empty());
+    }
+
+    #[test]
+    fn expose_body_tree() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="torso" pos="0 0 1">
+                     <joint name="hip" type="hinge"/>
+                     <geom name="torso_geom" size="0.1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let root = model.tree();
+        assert_eq!(root.name, WORLDBODY_NAME);
+        assert!(root.geoms.is_empty());
+        assert_eq!(root.children.len(), 1);
+
+        let torso = &root.children[0];
+        assert_eq!(torso.name, "torso");
+        assert_eq!(
+            torso.position.translation.vector,
+            Vector3::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(torso.geoms, vec![String::from("torso_geom")]);
+        assert_eq!(torso.joints, vec![String::from("hip")]);
+        assert!(torso.children.is_empty());
+    }
+
+    #[test]
+    fn enumerate_bodies_breadth_first() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <body name="left">
+                     <body name="left_child"/>
+                   </body>
+                   <body name="right"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let names: Vec<_> = model
+            .bodies_bfs()
+            .iter()
+            .map(|body| body.name.as_str())
+            .collect();
+        assert_eq!(names, vec![WORLDBODY_NAME, "left", "right", "left_child"]);
+    }
+
+    #[test]
+    fn body_source_range() {
+        let body_text = r#"<body name="arm">
+                     <geom size="0.1"/>
+                   </body>"#;
+        let text = format!(
+            "<mujoco>\n<worldbody>\n{}\n</worldbody>\n</mujoco>",
+            body_text
+        );
+        let model = MJCFModel::<f64>::parse_xml_string(&text).unwrap();
+
+        let range = model.body_source_range("arm").unwrap();
+        assert_eq!(&text[range], body_text);
+        assert_eq!(model.body_source_range(WORLDBODY_NAME), None);
+        assert_eq!(model.body_source_range("missing"), None);
+    }
+
+    #[test]
+    fn reject_duplicate_geom_names() {
+        let error = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ball" size="0.5"/>
+                   <geom name="ball" size="0.5"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::DuplicateGeomName(name) => assert_eq!(name, "ball"),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn attach_user_data() {
+        let mut model = MJCFModel::<f64>::parse_xml_string("<mujoco/>").unwrap();
+        assert!(model.user_data_ref::<String>().is_none());
+
+        model.set_user_data(String::from("models/arm.xml"));
+        assert_eq!(
+            model.user_data_ref::<String>().map(String::as_str),
+            Some("models/arm.xml")
+        );
+        assert!(model.user_data_ref::<u32>().is_none());
+    }
+
+    #[test]
+    fn replace_geom_shape() {
+        let mut model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ball" size="0.5" pos="0 0 1" rgba="1 0 0 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        model
+            .replace_geom_shape(
+                "ball",
+                ShapeHandle::new(Cuboid::new(Vector3::new(0.1, 0.2, 0.3))),
+            )
+            .unwrap();
+        assert_eq!(model.geom_shape_kind("ball"), Some(ShapeKind::Box));
+        let collider = model.get_collider("ball").unwrap();
+        assert!(collider.get_shape().is_shape::<Cuboid<f64>>());
+        assert_eq!(
+            collider.get_position().translation.vector,
+            Vector3::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            GeomUserData::from_collider_desc(collider).unwrap().rgba,
+            Point4::new(1.0, 0.0, 0.0, 1.0)
+        );
+
+        assert_eq!(
+            model.replace_geom_shape("missing", ShapeHandle::new(Ball::new(1.0))),
+            Err(UnknownGeomError(String::from("missing")))
+        );
+    }
+
+    #[test]
+    fn replace_ellipsoid_shape() {
+        let mut model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="egg" type="ellipsoid" size="0.1 0.2 0.3"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        model
+            .replace_geom_shape("egg", ShapeHandle::new(Ball::new(0.5)))
+            .unwrap();
+        assert_eq!(model.geom_shape_kind("egg"), Some(ShapeKind::Ball));
+        assert_eq!(
+            geom::ellipsoid_semi_axes(model.get_collider("egg").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn discard_visual_geoms() {
+        let text = r#"<mujoco>
+                        <compiler discardvisual="true"/>
+                        <worldbody>
+                          <body name="arm">
+                            <geom name="decal" size="0.1" contype="0" conaffinity="0"/>
+                            <geom size="0.1"/>
+                          </body>
+                        </worldbody>
+                      </mujoco>"#;
+        let model = MJCFModel::<f64>::parse_xml_string(text).unwrap();
+
+        assert!(model.get_collider("decal").is_none());
+        assert!(model.get_collider("geom1").is_some());
+        assert_eq!(
+            model.get_body("arm").unwrap().geoms,
+            vec![String::from("geom1")]
+        );
+
+        let model = MJCFModel::<f64>::parse_xml_string(&text.replace("true", "false")).unwrap();
+        assert!(model.get_collider("decal").is_some());
+    }
+
+    #[test]
+    fn reject_duplicate_names_of_discarded_geoms() {
+        let text = r#"<mujoco>
+                        <compiler discardvisual="true"/>
+                        <worldbody>
+                          <geom name="decal" size="0.1" contype="0" conaffinity="0"/>
+                          <geom name="decal" size="0.1"/>
+                        </worldbody>
+                      </mujoco>"#;
+
+        for discardvisual in &["true", "false"] {
+            let error = MJCFModel::<f64>::parse_xml_string(&text.replace("true", discardvisual))
+                .unwrap_err();
+            match error.kind {
+                MJCFParseErrorKind::DuplicateGeomName(name) => assert_eq!(name, "decal"),
+                kind => panic!("unexpected error kind {:?}", kind),
+            }
+        }
+    }
+
+    #[test]
+    fn only_record_unused_miscased_attributes() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ball" Pos="0 0 1" size="0.5" sparkle="yes"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_relative_eq!(
+            *model.get_collider("ball").unwrap().get_translation(),
+            Vector3::new(0.0, 0.0, 1.0)
+        );
+        let messages: Vec<_> = model
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["unsupported geom attribute 'sparkle'"]);
+    }
+
+    #[test]
+    fn skip_malformed_geom_in_lenient_mode() {
+        let text = r#"<mujoco>
+                        <worldbody>
+                          <geom name="bad" type="box" size="0.5"/>
+                          <geom name="good" size="0.5"/>
+                        </worldbody>
+                      </mujoco>"#;
+
+        let model = MJCFModel::<f64>::parse_xml_string(text).unwrap();
+        assert!(model.get_collider("bad").is_none());
+        assert!(model.get_collider("good").is_some());
+        assert_eq!(model.diagnostics().len(), 1);
+        let diagnostic = model.diagnostics().iter().next().unwrap();
+        assert_eq!(diagnostic.kind, DiagnosticKind::SkippedGeom);
+        assert_eq!(diagnostic.element, Some(String::from("bad")));
+
+        let strict = ParseOptions::builder().strict(true).build();
+        let error = MJCFModel::<f64>::parse_xml_string_with_options(text, &strict).unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::Geom(_) => {}
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn resolve_geom_rgba_from_material() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="inherited" size="0.5" material="red"/>
+                   <geom name="overridden" size="0.5" material="red" rgba="0 0 1 1"/>
+                   <geom name="plain" size="0.5"/>
+                 </worldbody>
+                 <asset>
+                   <material name="red" rgba="1 0 0 1"/>
+                 </asset>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let rgba = |name| {
+            GeomUserData::from_collider_desc(model.get_collider(name).unwrap())
+                .unwrap()
+                .rgba
+        };
+        assert_eq!(rgba("inherited"), Point4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(rgba("overridden"), Point4::new(0.0, 0.0, 1.0, 1.0));
+        assert_eq!(rgba("plain"), GeomUserData::default().rgba);
+    }
+
+    #[test]
+    fn resolve_geom_material_from_class() {
+        let text = r#"<mujoco>
+                        <default>
+                          <default class="painted">
+                            <geom material="red"/>
+                          </default>
+                          <default class="tinted">
+                            <geom material="red" rgba="0 1 0 1"/>
+                          </default>
+                        </default>
+                        <worldbody>
+                          <geom name="painted" class="painted" size="0.5"/>
+                          <geom name="tinted" class="tinted" size="0.5"/>
+                        </worldbody>
+                        <asset>
+                          <material name="red" rgba="1 0 0 1"/>
+                        </asset>
+                      </mujoco>"#;
+        let model = MJCFModel::<f64>::parse_xml_string(text).unwrap();
+
+        let rgba = |name| {
+            GeomUserData::from_collider_desc(model.get_collider(name).unwrap())
+                .unwrap()
+                .rgba
+        };
+        assert_eq!(rgba("painted"), Point4::new(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(rgba("tinted"), Point4::new(0.0, 1.0, 0.0, 1.0));
+
+        let error =
+            MJCFModel::<f64>::parse_xml_string(&text.replace(r#"name="red""#, r#"name="blue""#))
+                .unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::UnknownMaterial { geom, material } => {
+                assert_eq!(geom, "painted");
+                assert_eq!(material, "red");
+            }
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_texture_assets() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <asset>
+                   <texture type="skybox" builtin="gradient" width="100" height="100"/>
+                   <texture name="grid" builtin="checker" rgb1="1 1 1" rgb2="0 0 0"
+                            width="64" height="32"/>
+                 </asset>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.textures().len(), 2);
+        assert!(model.get_texture("texture0").is_some());
+        match model.get_texture("grid").unwrap() {
+            TextureAsset::Builtin {
+                rgb1,
+                rgb2,
+                width,
+                height,
+                ..
+            } => {
+                assert_eq!(*rgb1, Point3::new(1.0, 1.0, 1.0));
+                assert_eq!(*rgb2, Point3::new(0.0, 0.0, 0.0));
+                assert_eq!((*width, *height), (64, 32));
+            }
+            texture => panic!("unexpected texture {:?}", texture),
+        }
+    }
+
+    #[test]
+    fn lookup_materials() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <asset>
+                   <material name="red" rgba="1 0 0 1"/>
+                   <material name="blue" rgba="0 0 1 1"/>
+                 </asset>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.materials().len(), 2);
+        assert!(model.get_material("red").is_some());
+        assert!(model.get_material("blue").is_some());
+        assert!(model.get_material("green").is_none());
+    }
+
+    #[test]
+    fn parse_many_geoms() {
+        let geoms: String = (0..1000)
+            .map(|i| format!(r#"<body><geom size="0.1" pos="{} 0 0"/></body>"#, i))
+            .collect();
+        let model = MJCFModel::<f64>::parse_xml_string(&format!(
+            "<mujoco><worldbody>{}</worldbody></mujoco>",
+            geoms
+        ))
+        .unwrap();
+
+        assert_eq!(model.colliders().len(), 1000);
+        assert_eq!(model.bodies().len(), 1001);
+        assert_eq!(model.geom_body("geom999"), Some("body999"));
+        assert_eq!(
+            model
+                .get_collider("geom999")
+                .unwrap()
+                .get_position()
+                .translation
+                .vector,
+            Vector3::new(999.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn scope_geom_names_by_body() {
+        let text = r#"<mujoco>
+                        <worldbody>
+                          <body name="torso">
+                            <geom name="collision" size="0.1"/>
+                          </body>
+                          <body name="head">
+                            <geom name="collision" size="0.2"/>
+                            <geom size="0.05"/>
+                          </body>
+                        </worldbody>
+                      </mujoco>"#;
+        match MJCFModel::<f64>::parse_xml_string(text).unwrap_err().kind {
+            MJCFParseErrorKind::DuplicateGeomName(ref name) => assert_eq!(name, "collision"),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+
+        let options = ParseOptions::builder().scoped_geom_names(true).build();
+        let model = MJCFModel::<f64>::parse_xml_string_with_options(text, &options).unwrap();
+        assert!(model.get_collider("torso/collision").is_some());
+        assert!(model.get_collider("head/collision").is_some());
+        assert!(model.get_collider("head/geom2").is_some());
+        assert_eq!(model.geom_body("head/collision"), Some("head"));
+
+        let duplicate = text.replace(
+            r#"<geom size="0.05"/>"#,
+            r#"<geom name="collision" size="0.05"/>"#,
+        );
+        match MJCFModel::<f64>::parse_xml_string_with_options(&duplicate, &options)
+            .unwrap_err()
+            .kind
+        {
+            MJCFParseErrorKind::DuplicateGeomName(ref name) => {
+                assert_eq!(name, "head/collision")
+            }
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn lowercase_names() {
+        let text = r#"<mujoco>
+                        <asset>
+                          <material name="Red" rgba="1 0 0 1"/>
+                        </asset>
+                        <worldbody>
+                          <body name="Torso">
+                            <geom name="Foo" size="0.1" material="Red"/>
+                          </body>
+                        </worldbody>
+                      </mujoco>"#;
+        let options = ParseOptions::builder().lowercase_names(true).build();
+        let model = MJCFModel::<f64>::parse_xml_string_with_options(text, &options).unwrap();
+        assert!(model.get_collider("foo").is_some());
+        assert!(model.get_collider("Foo").is_none());
+        assert_eq!(model.geom_body("foo"), Some("torso"));
+
+        let duplicate = text.replace(
+            r#"<geom name="Foo" size="0.1" material="Red"/>"#,
+            r#"<geom name="Foo" size="0.1"/><geom name="foo" size="0.2"/>"#,
+        );
+        assert!(MJCFModel::<f64>::parse_xml_string(&duplicate).is_ok());
+        match MJCFModel::<f64>::parse_xml_string_with_options(&duplicate, &options)
+            .unwrap_err()
+            .kind
+        {
+            MJCFParseErrorKind::DuplicateGeomName(ref name) => assert_eq!(name, "foo"),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn inherit_geom_pos_from_default_class() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <default>
+                   <geom pos="0 0 1"/>
+                 </default>
+                 <worldbody>
+                   <geom name="inherited" size="0.5"/>
+                   <geom name="explicit" size="0.5" pos="1 0 0"/>
+                   <geom name="segment" type="capsule" size="0.1" fromto="0 0 0 2 0 0"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let translation = |name| {
+            model
+                .get_collider(name)
+                .unwrap()
+                .get_position()
+                .translation
+                .vector
+        };
+        assert_eq!(translation("inherited"), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(translation("explicit"), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(translation("segment"), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn inherit_geom_class_from_childclass() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <default>
+                   <default class="crate">
+                     <geom type="box" size="0.1 0.2 0.3"/>
+                   </default>
+                 </default>
+                 <worldbody>
+                   <body childclass="crate">
+                     <geom name="inherited"/>
+                     <geom name="explicit" class="main" size="0.5"/>
+                   </body>
+                   <geom name="outside" size="0.5"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let shape = |name| model.get_collider(name).unwrap().get_shape();
+        let cuboid = shape("inherited").as_shape::<Cuboid<f64>>().unwrap();
+        assert_eq!(cuboid.half_extents(), &Vector3::new(0.1, 0.2, 0.3));
+        assert!(shape("explicit").as_shape::<Ball<f64>>().is_some());
+        assert!(shape("outside").as_shape::<Ball<f64>>().is_some());
+    }
+
+    #[test]
+    fn capture_comments_as_documentation() {
+        let text = r#"<mujoco>
+                        <worldbody>
+                          <!-- Left wheel of the cart -->
+                          <geom name="wheel" size="0.1"/>
+                          <geom name="axle" size="0.1"/>
+                          <!-- The cart chassis -->
+                          <body name="chassis"/>
+                        </worldbody>
+                      </mujoco>"#;
+        let options = ParseOptions::builder().capture_comments(true).build();
+        let model = MJCFModel::<f64>::parse_xml_string_with_options(text, &options).unwrap();
+
+        let doc = |name| {
+            GeomUserData::from_collider_desc(model.get_collider(name).unwrap())
+                .unwrap()
+                .doc
+                .clone()
+        };
+        assert_eq!(doc("wheel"), Some(String::from("Left wheel of the cart")));
+        assert_eq!(doc("axle"), None);
+        assert_eq!(
+            model.get_body("chassis").unwrap().doc,
+            Some(String::from("The cart chassis"))
+        );
+
+        let model = MJCFModel::<f64>::parse_xml_string(text).unwrap();
+        let user_data =
+            GeomUserData::from_collider_desc(model.get_collider("wheel").unwrap()).unwrap();
+        assert_eq!(user_data.doc, None);
+    }
+
+    #[test]
+    fn flatten_nested_bodies() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="floor" type="plane" size="1 1 1"/>
+                   <body name="torso" pos="0 0 1">
+                     <joint type="free"/>
+                     <geom name="chest" size="0.1" pos="0 0 0.5"/>
+                     <body name="arm" pos="1 0 0" euler="0 0 90">
+                       <geom name="hand" size="0.1" pos="1 0 0"/>
+                     </body>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let flat_model = model.flatten();
+        assert_eq!(flat_model.bodies().len(), 1);
+        assert!(flat_model.joints().is_empty());
+        let world = flat_model.get_body(WORLDBODY_NAME).unwrap();
+        assert_eq!(world.geoms, vec!["floor", "chest", "hand"]);
+
+        let translation = |name| *flat_model.get_collider(name).unwrap().get_translation();
+        assert_relative_eq!(translation("floor"), Vector3::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(translation("chest"), Vector3::new(0.0, 0.0, 1.5));
+        assert_relative_eq!(
+            translation("hand"),
+            Vector3::new(1.0, 1.0, 1.0),
+            epsilon = 1.0e-10
+        );
+        assert_eq!(flat_model.geom_body("hand"), Some(WORLDBODY_NAME));
+    }
+
+    #[test]
+    fn store_geom_shape_kinds() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ball" size="0.5"/>
+                   <geom name="crate" type="box" size="0.1 0.2 0.3"/>
+                   <geom name="egg" type="ellipsoid" size="0.1 0.2 0.3"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(model.geom_shape_kind("ball"), Some(ShapeKind::Ball));
+        assert_eq!(model.geom_shape_kind("crate"), Some(ShapeKind::Box));
+        assert_eq!(model.geom_shape_kind("egg"), Some(ShapeKind::Ellipsoid));
+        assert_eq!(
+            geom::ellipsoid_semi_axes(model.get_collider("egg").unwrap()),
+            Some(Vector3::new(0.1, 0.2, 0.3))
+        );
+        assert_eq!(model.geom_shape_kind("missing"), None);
+    }
+
+    #[test]
+    fn reject_nested_unknown_elements() {
+        let text = r#"<mujoco>
+                        <worldbody>
+                          <body name="arm">
+                            <camera name="eye"/>
+                          </body>
+                        </worldbody>
+                      </mujoco>"#;
+        assert!(MJCFModel::<f64>::parse_xml_string(text).is_ok());
+
+        let options = ParseOptions::builder()
+            .strict_unknown_elements(true)
+            .build();
+        let error = MJCFModel::<f64>::parse_xml_string_with_options(text, &options).unwrap_err();
+        assert!(error.to_string().contains("mujoco/worldbody/body/camera"));
+        match error.kind {
+            MJCFParseErrorKind::UnknownElement(ref path) => {
+                assert_eq!(path, "mujoco/worldbody/body/camera")
+            }
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn parse_with_custom_logger() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let logger = slog::Logger::root(
+            MemoryDrain {
+                messages: messages.clone(),
+            },
+            o!(),
+        );
+
+        MJCFModel::<f64>::parse_xml_string_with_logger(r#"<mujoco><visual/></mujoco>"#, &logger)
+            .unwrap();
+
+        let messages = messages.lock().unwrap();
+        assert!(messages
+            .iter()
+            .any(|message| message == "Unsupported element. Ignoring"));
+    }
+
+    #[test]
+    fn warn_about_dynamics_of_worldbody_geoms() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let logger = slog::Logger::root(
+            MemoryDrain {
+                messages: messages.clone(),
+            },
+            o!(),
+        );
+
+        let model = MJCFModel::<f64>::parse_xml_string_with_logger(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="crate" type="box" size="1 1 1" density="500"/>
+                   <freejoint/>
+                 </worldbody>
+               </mujoco>"#,
+            &logger,
+        )
+        .unwrap();
+        // The geom is still added, just as a static one
+        assert_eq!(model.geom_body("crate"), Some(WORLDBODY_NAME));
+
+        let messages = messages.lock().unwrap();
+        assert!(messages
+            .iter()
+            .any(|message| message == "World body geoms are static, so their mass is ignored"));
+        assert!(messages.iter().any(|message| message
+            == "The world body is static, so it cannot move or have inertia. Ignoring"));
+    }
+
+    #[test]
+    fn warn_about_unsupported_integrator() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let logger = slog::Logger::root(
+            MemoryDrain {
+                messages: messages.clone(),
+            },
+            o!(),
+        );
+
+        let model = MJCFModel::<f64>::parse_xml_string_with_logger(
+            r#"<mujoco><option timestep="0.01" integrator="RK4"/></mujoco>"#,
+            &logger,
+        )
+        .unwrap();
+        assert_eq!(model.options().timestep, 0.01);
+
+        let messages = messages.lock().unwrap();
+        assert!(messages.iter().any(|message| message
+            == "Integrator is not supported by nphysics. Using semi-implicit Euler"));
+    }
+}
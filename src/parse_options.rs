@@ -0,0 +1,155 @@
+use na::Real;
+use nalgebra as na;
+
+/// Settings controlling how a model is parsed.
+///
+/// Use `ParseOptions::builder()` to override individual settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    /// Reject constructs MuJoCo would reject instead of warning and
+    /// continuing.
+    pub strict: bool,
+    /// Factor applied to every position and size in the model, e.g.
+    /// `0.001` for a model authored in millimeters.
+    pub length_scale: f64,
+    /// Attach the text of a comment directly preceding a named geom or
+    /// body to its descriptor as documentation.
+    pub capture_comments: bool,
+    /// Tolerance below which quaternions, axes and fromto segments are
+    /// rejected as degenerate.
+    pub epsilon: f64,
+    /// Reject elements the parser does not know at any depth instead of
+    /// warning and ignoring them.
+    pub strict_unknown_elements: bool,
+    /// Store geoms under their name qualified by the name of their body,
+    /// e.g. `torso/collision`, so that bodies can reuse geom names.
+    pub scoped_geom_names: bool,
+    /// How deep `include` elements may be nested when parsing a model
+    /// file. Deeper includes, e.g. from an include cycle, are rejected.
+    pub max_include_depth: usize,
+    /// Store geom, body and material names in lowercase, so that names
+    /// differing only in case are reported as duplicates.
+    pub lowercase_names: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict: false,
+            length_scale: 1.0,
+            capture_comments: false,
+            epsilon: 1e-10,
+            strict_unknown_elements: false,
+            scoped_geom_names: false,
+            max_include_depth: 32,
+            lowercase_names: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    pub fn builder() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+
+    pub(crate) fn degenerate_epsilon<N: Real>(&self) -> N {
+        na::convert(self.epsilon)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `length_scale` is not positive.
+    pub fn length_scale(mut self, length_scale: f64) -> Self {
+        assert!(length_scale > 0.0, "length_scale must be positive");
+        self.options.length_scale = length_scale;
+        self
+    }
+
+    pub fn capture_comments(mut self, capture_comments: bool) -> Self {
+        self.options.capture_comments = capture_comments;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `epsilon` is negative.
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        assert!(epsilon >= 0.0, "epsilon must not be negative");
+        self.options.epsilon = epsilon;
+        self
+    }
+
+    pub fn strict_unknown_elements(mut self, strict_unknown_elements: bool) -> Self {
+        self.options.strict_unknown_elements = strict_unknown_elements;
+        self
+    }
+
+    pub fn scoped_geom_names(mut self, scoped_geom_names: bool) -> Self {
+        self.options.scoped_geom_names = scoped_geom_names;
+        self
+    }
+
+    pub fn max_include_depth(mut self, max_include_depth: usize) -> Self {
+        self.options.max_include_depth = max_include_depth;
+        self
+    }
+
+    pub fn lowercase_names(mut self, lowercase_names: bool) -> Self {
+        self.options.lowercase_names = lowercase_names;
+        self
+    }
+
+    pub fn build(self) -> ParseOptions {
+        self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_options() {
+        let options = ParseOptions::builder()
+            .strict(true)
+            .length_scale(0.001)
+            .capture_comments(true)
+            .epsilon(1e-6)
+            .strict_unknown_elements(true)
+            .scoped_geom_names(true)
+            .max_include_depth(4)
+            .lowercase_names(true)
+            .build();
+        assert!(options.strict);
+        assert_eq!(options.length_scale, 0.001);
+        assert!(options.capture_comments);
+        assert_eq!(options.epsilon, 1e-6);
+        assert!(options.strict_unknown_elements);
+        assert!(options.scoped_geom_names);
+        assert_eq!(options.max_include_depth, 4);
+        assert!(options.lowercase_names);
+    }
+
+    #[test]
+    fn builder_defaults() {
+        assert_eq!(ParseOptions::builder().build(), ParseOptions::default());
+    }
+
+    #[test]
+    #[should_panic]
+    fn reject_non_positive_length_scale() {
+        ParseOptions::builder().length_scale(0.0);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::mjcf_model::MJCFModel;
+use crate::tags::geom::{GeomUserData, ShapeKind};
+use na::{Point3, Real, Vector3};
+use nalgebra as na;
+use ncollide3d::bounding_volume::{BoundingVolume, AABB};
+use nphysics3d::object::ColliderDesc;
+
+impl<N: Real> MJCFModel<N> {
+    /// Names of the geoms whose world space AABB overlaps `aabb`, in
+    /// document order.
+    pub fn colliders_in_aabb(&self, aabb: &AABB<N>) -> Vec<&str> {
+        self.iter_geoms()
+            .filter(|(name, collider)| {
+                let position = self
+                    .geom_world_position(name)
+                    .expect("Every geom must belong to a body");
+                collider.get_shape().aabb(&position).intersects(aabb)
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Name of the first geom in document order whose shape contains
+    /// `point`.
+    pub fn geom_at_point(&self, point: Point3<N>) -> Option<&str> {
+        self.iter_geoms()
+            .find(|(name, collider)| {
+                let position = self
+                    .geom_world_position(name)
+                    .expect("Every geom must belong to a body");
+                collider
+                    .get_shape()
+                    .as_point_query()
+                    .map_or(false, |shape| shape.contains_point(&position, &point))
+            })
+            .map(|(name, _)| name)
+    }
+
+    /// The first geom in document order with a shape of the given kind.
+    pub fn first_of_kind(&self, kind: ShapeKind) -> Option<(&str, &ColliderDesc<N>)> {
+        self.iter_geoms()
+            .find(|(name, _)| self.geom_shape_kind(name) == Some(kind))
+    }
+
+    /// Friction MuJoCo uses for a contact between the two geoms, taking
+    /// their priorities into account.
+    ///
+    /// nphysics combines the materials of both colliders on its own, so
+    /// `build` only gets this friction for contacts whose geoms each have
+    /// the same friction in all of their contacts.
+    pub fn contact_friction(&self, geom1: &str, geom2: &str) -> Option<Vector3<N>> {
+        let user_data1 = GeomUserData::from_collider_desc(self.get_collider(geom1)?)?;
+        let user_data2 = GeomUserData::from_collider_desc(self.get_collider(geom2)?)?;
+        Some(user_data1.mixed_friction(user_data2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_colliders_in_aabb() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="near" size="1"/>
+                   <body pos="10 0 0">
+                     <geom name="far" size="1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let aabb = AABB::new(Point3::new(-2.0, -2.0, -2.0), Point3::new(2.0, 2.0, 2.0));
+        assert_eq!(model.colliders_in_aabb(&aabb), vec!["near"]);
+
+        let aabb = AABB::new(Point3::new(8.5, -2.0, -2.0), Point3::new(9.5, 2.0, 2.0));
+        assert_eq!(model.colliders_in_aabb(&aabb), vec!["far"]);
+    }
+
+    #[test]
+    fn query_geom_at_point() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ball" size="1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            model.geom_at_point(Point3::new(0.5, 0.0, 0.0)),
+            Some("ball")
+        );
+        assert_eq!(model.geom_at_point(Point3::new(1.5, 0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn query_first_of_kind() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="crate" type="box" size="1 1 1"/>
+                   <geom name="ball" size="1"/>
+                   <geom name="other_crate" type="box" size="1 1 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        let (name, _) = model.first_of_kind(ShapeKind::Box).unwrap();
+        assert_eq!(name, "crate");
+        let (name, _) = model.first_of_kind(ShapeKind::Ball).unwrap();
+        assert_eq!(name, "ball");
+        assert!(model.first_of_kind(ShapeKind::Plane).is_none());
+    }
+
+    #[test]
+    fn use_friction_of_higher_priority_geom() {
+        let model = MJCFModel::<f64>::parse_xml_string(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="ice" size="1" friction="0.1 0.01 0.001" priority="1"/>
+                   <geom name="rubber" size="1" friction="2 0.02 0.002"/>
+                   <geom name="steel" size="1" friction="0.5 0.05 0.0001"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            model.contact_friction("ice", "rubber"),
+            Some(Vector3::new(0.1, 0.01, 0.001))
+        );
+        assert_eq!(
+            model.contact_friction("rubber", "ice"),
+            Some(Vector3::new(0.1, 0.01, 0.001))
+        );
+        assert_eq!(
+            model.contact_friction("rubber", "steel"),
+            Some(Vector3::new(2.0, 0.05, 0.002))
+        );
+        assert_eq!(model.contact_friction("ice", "missing"), None);
+    }
+}
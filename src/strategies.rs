@@ -0,0 +1,62 @@
+//! proptest strategies generating valid MJCF elements.
+
+use proptest::prelude::*;
+
+/// A generated geom element together with the values written into it.
+#[derive(Debug, Clone)]
+pub struct GeomXml {
+    pub xml: String,
+    pub geom_type: &'static str,
+    pub size: Vec<f64>,
+    pub pos: [f64; 3],
+}
+
+fn size_component() -> impl Strategy<Value = f64> {
+    0.001f64..100.0
+}
+
+fn pos() -> impl Strategy<Value = [f64; 3]> {
+    [-100.0f64..100.0, -100.0f64..100.0, -100.0f64..100.0]
+}
+
+fn geom_xml(geom_type: &'static str, size: Vec<f64>, pos: [f64; 3]) -> GeomXml {
+    let size_text = size
+        .iter()
+        .map(|component| component.to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+    GeomXml {
+        xml: format!(
+            r#"<geom type="{}" size="{}" pos="{} {} {}"/>"#,
+            geom_type, size_text, pos[0], pos[1], pos[2]
+        ),
+        geom_type,
+        size,
+        pos,
+    }
+}
+
+pub fn sphere_geom() -> impl Strategy<Value = GeomXml> {
+    (size_component(), pos()).prop_map(|(radius, pos)| geom_xml("sphere", vec![radius], pos))
+}
+
+pub fn box_geom() -> impl Strategy<Value = GeomXml> {
+    (prop::collection::vec(size_component(), 3), pos())
+        .prop_map(|(half_extents, pos)| geom_xml("box", half_extents, pos))
+}
+
+/// Capsules given by their radius and half length.
+pub fn capsule_geom() -> impl Strategy<Value = GeomXml> {
+    (prop::collection::vec(size_component(), 2), pos())
+        .prop_map(|(size, pos)| geom_xml("capsule", size, pos))
+}
+
+/// Cylinders given by their radius and half length.
+pub fn cylinder_geom() -> impl Strategy<Value = GeomXml> {
+    (prop::collection::vec(size_component(), 2), pos())
+        .prop_map(|(size, pos)| geom_xml("cylinder", size, pos))
+}
+
+pub fn any_geom() -> impl Strategy<Value = GeomXml> {
+    prop_oneof![sphere_geom(), box_geom(), capsule_geom(), cylinder_geom()]
+}
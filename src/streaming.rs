@@ -0,0 +1,291 @@
+use crate::log;
+use crate::mjcf_model::{MJCFParseErrorKind, MJCFParseResult, WORLDBODY_NAME};
+use crate::parse_options::ParseOptions;
+use crate::tags::compiler::{self, CompilerOptions};
+use crate::tags::default::{self, Defaults, MAIN_CLASS};
+use crate::tags::geom;
+use na::Real;
+use nalgebra as na;
+use nphysics3d::object::ColliderDesc;
+use roxmltree;
+use slog;
+use std::collections::HashMap;
+
+/// Receives the elements of a model as `parse_streaming` walks the
+/// document.
+///
+/// All methods do nothing by default, so handlers only need to
+/// implement the events they are interested in.
+pub trait MjcfHandler<N: Real> {
+    /// Called before any of the body's children. Body positions are
+    /// relative to the parent body, exactly as they are written in the
+    /// document.
+    fn on_body_start(&mut self, _name: &str, _parent: &str) {}
+
+    /// Called after all of the body's children.
+    fn on_body_end(&mut self, _name: &str) {}
+
+    /// Called with the collider of every geom. The collider position is
+    /// relative to the containing body.
+    fn on_geom(&mut self, _name: &str, _body: &str, _collider: ColliderDesc<N>) {}
+}
+
+/// Parse a model by emitting an event for every body and geom instead of
+/// building an `MJCFModel`.
+///
+/// Nothing is kept once it has been passed to `handler`, which keeps
+/// memory usage low for huge generated scenes. Elements are named the
+/// same way `MJCFModel` names them, and geoms take their attributes from
+/// default classes and the `childclass` of their bodies like in
+/// `MJCFModel`. Names are not checked for duplicates and assets are not
+/// resolved.
+pub fn parse_streaming<N: Real>(
+    text: &str,
+    handler: &mut dyn MjcfHandler<N>,
+) -> MJCFParseResult<()> {
+    let logger = log::get_root_logger();
+    let doc = roxmltree::Document::parse(text)?;
+
+    let root = doc.root_element();
+    if !root.has_tag_name("mujoco") {
+        return Err(MJCFParseErrorKind::NotMuJoCoModel(root.tag_name().name().to_string()).into());
+    }
+
+    let mut parser = StreamingParser {
+        logger: logger.clone(),
+        options: ParseOptions::default(),
+        compiler: CompilerOptions::default(),
+        defaults: Defaults::default(),
+        childclass_stack: vec![],
+        handler,
+        num_geoms: 0,
+        num_bodies: 0,
+    };
+
+    // Like in `MJCFModel`, the compiler settings and default classes
+    // apply regardless of where they appear in the document
+    for child in root.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "compiler" => parser.compiler = compiler::parse_compiler_node(&logger, &child)?,
+            "default" => {
+                default::parse_default_node(&logger, &parser.options, &child, &mut parser.defaults)?
+            }
+            _ => {}
+        }
+    }
+    for child in root.children().filter(|child| child.is_element()) {
+        if child.has_tag_name("worldbody") {
+            parser.parse_body_children(&child, WORLDBODY_NAME)?;
+        }
+    }
+
+    Ok(())
+}
+
+struct StreamingParser<'a, N: Real> {
+    logger: slog::Logger,
+    options: ParseOptions,
+    compiler: CompilerOptions<N>,
+    defaults: Defaults,
+    /// Classes set by the `childclass` of the bodies currently being
+    /// parsed, innermost last.
+    childclass_stack: Vec<String>,
+    handler: &'a mut dyn MjcfHandler<N>,
+    num_geoms: usize,
+    num_bodies: usize,
+}
+
+impl<'a, N: Real> StreamingParser<'a, N> {
+    fn parse_body_children(
+        &mut self,
+        body_node: &roxmltree::Node,
+        body_name: &str,
+    ) -> MJCFParseResult<()> {
+        for child in body_node.children().filter(|child| child.is_element()) {
+            match child.tag_name().name() {
+                "geom" => self.parse_geom(&child, body_name)?,
+                "body" => self.parse_body(&child, body_name)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_body(
+        &mut self,
+        body_node: &roxmltree::Node,
+        parent_name: &str,
+    ) -> MJCFParseResult<()> {
+        let name = match body_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("body{}", self.num_bodies),
+        };
+        self.num_bodies += 1;
+
+        self.handler.on_body_start(&name, parent_name);
+        if let Some(childclass) = body_node.attribute("childclass") {
+            if self.defaults.get_class(childclass).is_none() {
+                return Err(MJCFParseErrorKind::UnknownClass(childclass.to_string()).into());
+            }
+            self.childclass_stack.push(childclass.to_string());
+        }
+        self.parse_body_children(body_node, &name)?;
+        if body_node.has_attribute("childclass") {
+            self.childclass_stack.pop();
+        }
+        self.handler.on_body_end(&name);
+
+        Ok(())
+    }
+
+    fn parse_geom(&mut self, geom_node: &roxmltree::Node, body_name: &str) -> MJCFParseResult<()> {
+        let name = match geom_node.attribute("name") {
+            Some(name) => name.to_string(),
+            None => format!("geom{}", self.num_geoms),
+        };
+        self.num_geoms += 1;
+
+        let logger = self.logger.new(o!("geom" => name.clone()));
+        // An explicit class takes precedence over the childclass of the
+        // enclosing bodies
+        let class = geom_node
+            .attribute("class")
+            .or_else(|| self.childclass_stack.last().map(String::as_str))
+            .unwrap_or(MAIN_CLASS);
+        let defaults = self
+            .defaults
+            .geom_attributes(class)
+            .ok_or_else(|| MJCFParseErrorKind::UnknownClass(class.to_string()))?;
+        let collider = geom::parse_geom_node_with_defaults(
+            &logger,
+            &self.options,
+            &self.compiler,
+            geom_node,
+            defaults,
+            &HashMap::new(),
+        )?;
+        self.handler.on_geom(&name, body_name, collider);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::Vector3;
+    use ncollide3d::shape::{Ball, Cuboid};
+
+    #[derive(Default)]
+    struct CountingHandler {
+        geoms: usize,
+        open_bodies: Vec<String>,
+        bodies: usize,
+    }
+
+    impl MjcfHandler<f64> for CountingHandler {
+        fn on_body_start(&mut self, name: &str, _parent: &str) {
+            self.open_bodies.push(name.to_string());
+        }
+
+        fn on_body_end(&mut self, name: &str) {
+            assert_eq!(
+                self.open_bodies.pop().as_ref().map(String::as_str),
+                Some(name)
+            );
+            self.bodies += 1;
+        }
+
+        fn on_geom(&mut self, _name: &str, _body: &str, _collider: ColliderDesc<f64>) {
+            self.geoms += 1;
+        }
+    }
+
+    #[test]
+    fn count_geom_events() {
+        let mut handler = CountingHandler::default();
+        parse_streaming(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom type="plane" size="1 1 1"/>
+                   <geom size="0.1" pos="0 0 1"/>
+                   <body pos="0 0 2">
+                     <geom size="0.1"/>
+                     <body>
+                       <geom type="box" size="0.1 0.1 0.1"/>
+                     </body>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+            &mut handler,
+        )
+        .unwrap();
+
+        assert_eq!(handler.geoms, 4);
+        assert_eq!(handler.bodies, 2);
+        assert!(handler.open_bodies.is_empty());
+    }
+
+    #[derive(Default)]
+    struct ColliderHandler {
+        colliders: HashMap<String, ColliderDesc<f64>>,
+    }
+
+    impl MjcfHandler<f64> for ColliderHandler {
+        fn on_geom(&mut self, name: &str, _body: &str, collider: ColliderDesc<f64>) {
+            self.colliders.insert(name.to_string(), collider);
+        }
+    }
+
+    #[test]
+    fn apply_default_classes() {
+        let mut handler = ColliderHandler::default();
+        parse_streaming(
+            r#"<mujoco>
+                 <default>
+                   <geom size="0.5"/>
+                   <default class="crate">
+                     <geom type="box" size="0.1 0.2 0.3"/>
+                   </default>
+                 </default>
+                 <worldbody>
+                   <geom name="ball"/>
+                   <geom name="explicit_crate" class="crate"/>
+                   <body childclass="crate">
+                     <geom name="inherited_crate"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+            &mut handler,
+        )
+        .unwrap();
+
+        let ball = handler.colliders["ball"].get_shape();
+        assert_eq!(ball.as_shape::<Ball<f64>>().unwrap().radius(), 0.5);
+        for name in &["explicit_crate", "inherited_crate"] {
+            let shape = handler.colliders[*name].get_shape();
+            assert_eq!(
+                *shape.as_shape::<Cuboid<f64>>().unwrap().half_extents(),
+                Vector3::new(0.1, 0.2, 0.3)
+            );
+        }
+    }
+
+    #[test]
+    fn reject_unknown_childclass() {
+        let error = parse_streaming::<f64>(
+            r#"<mujoco>
+                 <worldbody>
+                   <body childclass="missing"/>
+                 </worldbody>
+               </mujoco>"#,
+            &mut ColliderHandler::default(),
+        )
+        .unwrap_err();
+        match error.kind {
+            MJCFParseErrorKind::UnknownClass(class) => assert_eq!(class, "missing"),
+            kind => panic!("unexpected error {:?}", kind),
+        }
+    }
+}
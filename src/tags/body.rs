@@ -0,0 +1,250 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{self, Attr, AttrError, ParseOrientationError, ParseRealVectorError};
+use crate::parse_options::ParseOptions;
+use crate::tags::compiler::CompilerOptions;
+use na::{Isometry3, Real, Translation3, Vector3};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+use std::num::ParseFloatError;
+use std::ops::Range;
+
+pub(crate) const SUPPORTED_ATTRIBUTES: &[&str] = &[
+    "name",
+    "childclass",
+    "gravcomp",
+    "user",
+    "pos",
+    "quat",
+    "axisangle",
+    "euler",
+    "xyaxes",
+    "zaxis",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyDesc<N: Real> {
+    pub name: String,
+    /// `None` only for the world body.
+    pub parent: Option<String>,
+    /// Position relative to the parent body's frame.
+    pub position: Isometry3<N>,
+    pub geoms: Vec<String>,
+    pub joints: Vec<String>,
+    pub sites: Vec<String>,
+    pub children: Vec<String>,
+    /// Index of the body in document order. The world body is first.
+    pub order: usize,
+    /// Comment preceding the body, if comments are captured.
+    pub doc: Option<String>,
+    /// Fraction of the body's weight that is canceled by an opposing
+    /// force.
+    pub gravcomp: N,
+    /// Byte range of the `body` element in the parsed text. `None` for
+    /// the world body, which may be split over several elements.
+    pub source_range: Option<Range<usize>>,
+    /// Custom numbers MuJoCo passes through without interpreting them.
+    pub user: Vec<N>,
+}
+
+impl<N: Real> BodyDesc<N> {
+    pub(crate) fn new(
+        name: String,
+        parent: Option<String>,
+        position: Isometry3<N>,
+        order: usize,
+    ) -> Self {
+        BodyDesc {
+            name,
+            parent,
+            position,
+            geoms: vec![],
+            joints: vec![],
+            sites: vec![],
+            children: vec![],
+            order,
+            doc: None,
+            gravcomp: N::zero(),
+            source_range: None,
+            user: vec![],
+        }
+    }
+}
+
+/// A body and its descendants, with the names of the elements attached
+/// to each body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyNode<N: Real> {
+    pub name: String,
+    /// Position relative to the parent body's frame.
+    pub position: Isometry3<N>,
+    pub geoms: Vec<String>,
+    pub sites: Vec<String>,
+    pub joints: Vec<String>,
+    /// Child bodies in document order.
+    pub children: Vec<BodyNode<N>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyError {
+    InvalidReal {
+        attribute: &'static str,
+        error: ParseFloatError,
+    },
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidOrientation(ParseOrientationError),
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BodyError::InvalidReal { attribute, error } => {
+                write!(f, "invalid body {}: {}", attribute, error)
+            }
+            BodyError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid body {}: {}", attribute, error)
+            }
+            BodyError::InvalidOrientation(error) => {
+                write!(f, "invalid body orientation: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for BodyError {}
+
+impl From<AttrError> for BodyError {
+    fn from(error: AttrError) -> Self {
+        match error {
+            AttrError::InvalidReal { attribute, error } => {
+                BodyError::InvalidReal { attribute, error }
+            }
+            AttrError::InvalidRealVector { attribute, error } => {
+                BodyError::InvalidRealVector { attribute, error }
+            }
+            // Bodies have no boolean or integer attributes yet
+            error => unreachable!("unexpected body attribute error {:?}", error),
+        }
+    }
+}
+
+impl From<ParseOrientationError> for BodyError {
+    fn from(error: ParseOrientationError) -> Self {
+        BodyError::InvalidOrientation(error)
+    }
+}
+
+/// Parse the attributes of a `body` element.
+///
+/// Geoms, joints and child bodies are named uniquely across the whole
+/// model, so they are parsed by the model, which adds them to the
+/// returned descriptor.
+pub fn parse_body_node<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &roxmltree::Node,
+    name: String,
+    parent: &str,
+    order: usize,
+) -> Result<BodyDesc<N>, BodyError> {
+    warn_unsupported_attributes(logger, node, SUPPORTED_ATTRIBUTES);
+
+    let translation = Attr::vec3(options, node, "pos")?.unwrap_or_else(Vector3::zeros)
+        * na::convert::<f64, N>(options.length_scale);
+    let rotation = attributes::parse_orientation_attribute(options, compiler, node)?;
+    let position = Isometry3::from_parts(
+        Translation3::new(translation.x, translation.y, translation.z),
+        rotation,
+    );
+
+    let mut body = BodyDesc::new(name, Some(parent.to_string()), position, order);
+    if let Some(gravcomp) = Attr::real(node, "gravcomp")? {
+        body.gravcomp = gravcomp;
+    }
+    if let Some(user) = node.attribute("user") {
+        body.user = user
+            .split_whitespace()
+            .map(attributes::parse_real_attribute)
+            .collect::<Result<_, _>>()
+            .map_err(|error| BodyError::InvalidRealVector {
+                attribute: "user",
+                error: error.into(),
+            })?;
+    }
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    fn parse_body(text: &str) -> Result<BodyDesc<f64>, BodyError> {
+        let doc = roxmltree::Document::parse(text).unwrap();
+        parse_body_node(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+            String::from("arm"),
+            "world",
+            1,
+        )
+    }
+
+    #[test]
+    fn parse_body_frame() {
+        let body = parse_body(
+            r#"<body name="arm" pos="1 2 3" quat="0.7071068 0 0 0.7071068">
+                 <geom size="0.1"/>
+               </body>"#,
+        )
+        .unwrap();
+
+        assert_eq!(body.name, "arm");
+        assert_eq!(body.parent, Some(String::from("world")));
+        assert_eq!(body.order, 1);
+        assert_eq!(
+            body.position.translation.vector,
+            Vector3::new(1.0, 2.0, 3.0)
+        );
+        assert_relative_eq!(
+            body.position.rotation * Vector3::x(),
+            Vector3::y(),
+            epsilon = 1e-6
+        );
+        // The child geom is added by the model
+        assert!(body.geoms.is_empty());
+    }
+
+    #[test]
+    fn parse_body_user_numbers() {
+        let body = parse_body(r#"<body user="0.5 -1"/>"#).unwrap();
+        assert_eq!(body.user, vec![0.5, -1.0]);
+    }
+
+    #[test]
+    fn reject_invalid_body_pos() {
+        assert_eq!(
+            parse_body(r#"<body pos="1 2"/>"#).unwrap_err(),
+            BodyError::InvalidRealVector {
+                attribute: "pos",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 3,
+                    actual: 2
+                }
+            }
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{parse_bool_attribute, parse_real_attribute, ParseBoolAttributeError};
+use na::Real;
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+use std::num::ParseFloatError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AngleUnit {
+    Degree,
+    Radian,
+}
+
+impl Default for AngleUnit {
+    fn default() -> Self {
+        AngleUnit::Degree
+    }
+}
+
+/// Frame positions and orientations are given in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coordinate {
+    /// Relative to the parent body.
+    Local,
+    /// In the world frame. Removed in MuJoCo 2.3.3.
+    Global,
+}
+
+impl Default for Coordinate {
+    fn default() -> Self {
+        Coordinate::Local
+    }
+}
+
+/// Compiler settings that indicate which MuJoCo version a model was
+/// written for.
+///
+/// MJCF has no version attribute, so tools have to infer compatibility
+/// from the settings themselves. `autolimits` was added in MuJoCo 2.2.2
+/// and `coordinate="global"` was removed in 2.3.3.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelMeta {
+    pub coordinate: Coordinate,
+    pub angle: AngleUnit,
+    /// `None` if the model does not set `autolimits`.
+    pub autolimits: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompilerOptions<N: Real> {
+    pub angle: AngleUnit,
+    /// Only recorded. Models with global coordinates are parsed as if
+    /// they were local.
+    pub coordinate: Coordinate,
+    /// `None` if not given.
+    pub autolimits: Option<bool>,
+    /// Lower bound applied to the mass of every body except the world.
+    pub boundmass: N,
+    /// Lower bound applied to the diagonal of every body's inertia.
+    pub boundinertia: N,
+    /// Target of the total mass of all bodies. Geom densities are
+    /// scaled to reach it.
+    pub settotalmass: Option<N>,
+    /// Drop geoms that cannot collide with anything, since they are
+    /// only used for rendering.
+    pub discardvisual: bool,
+    /// Factor applied to the size of primitive geoms fitted to a mesh.
+    pub fitscale: N,
+}
+
+impl<N: Real> Default for CompilerOptions<N> {
+    fn default() -> Self {
+        CompilerOptions {
+            angle: AngleUnit::default(),
+            coordinate: Coordinate::default(),
+            autolimits: None,
+            boundmass: N::zero(),
+            boundinertia: N::zero(),
+            settotalmass: None,
+            discardvisual: false,
+            fitscale: N::one(),
+        }
+    }
+}
+
+impl<N: Real> CompilerOptions<N> {
+    /// Convert an angle given in the model's angle unit to radians.
+    pub fn angle_to_radians(&self, angle: N) -> N {
+        match self.angle {
+            AngleUnit::Degree => angle * N::pi() / na::convert(180.0),
+            AngleUnit::Radian => angle,
+        }
+    }
+
+    pub fn meta(&self) -> ModelMeta {
+        ModelMeta {
+            coordinate: self.coordinate,
+            angle: self.angle,
+            autolimits: self.autolimits,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompilerError {
+    UnknownAngleUnit(String),
+    UnknownCoordinate(String),
+    InvalidReal {
+        attribute: &'static str,
+        error: ParseFloatError,
+    },
+    NegativeBound(&'static str),
+    NonPositiveTotalMass,
+    NonPositiveFitScale,
+    InvalidBool {
+        attribute: &'static str,
+        error: ParseBoolAttributeError,
+    },
+}
+
+impl fmt::Display for CompilerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompilerError::UnknownAngleUnit(unit) => write!(
+                f,
+                "unknown angle unit '{}'. Expected 'degree' or 'radian'",
+                unit
+            ),
+            CompilerError::UnknownCoordinate(coordinate) => write!(
+                f,
+                "unknown coordinate mode '{}'. Expected 'local' or 'global'",
+                coordinate
+            ),
+            CompilerError::InvalidReal { attribute, error } => {
+                write!(f, "invalid compiler {}: {}", attribute, error)
+            }
+            CompilerError::NegativeBound(attribute) => {
+                write!(f, "compiler {} cannot be negative", attribute)
+            }
+            CompilerError::NonPositiveTotalMass => {
+                write!(f, "compiler settotalmass must be positive")
+            }
+            CompilerError::NonPositiveFitScale => write!(f, "compiler fitscale must be positive"),
+            CompilerError::InvalidBool { attribute, error } => {
+                write!(f, "invalid compiler {}: {}", attribute, error)
+            }
+        }
+    }
+}
+
+impl Error for CompilerError {}
+
+pub fn parse_compiler_node<N: Real>(
+    logger: &slog::Logger,
+    node: &roxmltree::Node,
+) -> Result<CompilerOptions<N>, CompilerError> {
+    warn_unsupported_attributes(
+        logger,
+        node,
+        &[
+            "angle",
+            "coordinate",
+            "autolimits",
+            "boundmass",
+            "boundinertia",
+            "settotalmass",
+            "discardvisual",
+            "fitscale",
+        ],
+    );
+
+    let mut compiler = CompilerOptions::default();
+
+    if let Some(angle) = node.attribute("angle") {
+        compiler.angle = match angle {
+            "degree" => AngleUnit::Degree,
+            "radian" => AngleUnit::Radian,
+            _ => return Err(CompilerError::UnknownAngleUnit(angle.to_string())),
+        };
+    }
+
+    if let Some(coordinate) = node.attribute("coordinate") {
+        compiler.coordinate = match coordinate {
+            "local" => Coordinate::Local,
+            "global" => {
+                // TODO(dschwab): Convert global frames to local ones
+                warn!(
+                    logger,
+                    "Global coordinates are not supported. Treating them as local"
+                );
+                Coordinate::Global
+            }
+            _ => return Err(CompilerError::UnknownCoordinate(coordinate.to_string())),
+        };
+    }
+
+    if let Some(autolimits) = node.attribute("autolimits") {
+        compiler.autolimits =
+            parse_bool_attribute(autolimits).map_err(|error| CompilerError::InvalidBool {
+                attribute: "autolimits",
+                error,
+            })?;
+    }
+
+    compiler.boundmass = parse_bound(node, "boundmass")?;
+    compiler.boundinertia = parse_bound(node, "boundinertia")?;
+
+    if let Some(settotalmass) = node.attribute("settotalmass") {
+        let settotalmass =
+            parse_real_attribute(settotalmass).map_err(|error| CompilerError::InvalidReal {
+                attribute: "settotalmass",
+                error,
+            })?;
+        if settotalmass <= N::zero() {
+            return Err(CompilerError::NonPositiveTotalMass);
+        }
+        compiler.settotalmass = Some(settotalmass);
+    }
+
+    if let Some(discardvisual) = node.attribute("discardvisual") {
+        compiler.discardvisual = parse_bool_attribute(discardvisual)
+            .map_err(|error| CompilerError::InvalidBool {
+                attribute: "discardvisual",
+                error,
+            })?
+            .unwrap_or(false);
+    }
+
+    if let Some(fitscale) = node.attribute("fitscale") {
+        let fitscale =
+            parse_real_attribute(fitscale).map_err(|error| CompilerError::InvalidReal {
+                attribute: "fitscale",
+                error,
+            })?;
+        if fitscale <= N::zero() {
+            return Err(CompilerError::NonPositiveFitScale);
+        }
+        compiler.fitscale = fitscale;
+    }
+
+    Ok(compiler)
+}
+
+fn parse_bound<N: Real>(
+    node: &roxmltree::Node,
+    attribute: &'static str,
+) -> Result<N, CompilerError> {
+    let bound = match node.attribute(attribute) {
+        Some(bound) => parse_real_attribute(bound)
+            .map_err(|error| CompilerError::InvalidReal { attribute, error })?,
+        None => return Ok(N::zero()),
+    };
+    if bound < N::zero() {
+        return Err(CompilerError::NegativeBound(attribute));
+    }
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    #[test]
+    fn parse_angle_unit() {
+        let doc = roxmltree::Document::parse(r#"<compiler angle="radian"/>"#).unwrap();
+        let compiler = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap();
+        assert_eq!(compiler.angle, AngleUnit::Radian);
+
+        let doc = roxmltree::Document::parse(r#"<compiler angle="gradian"/>"#).unwrap();
+        let error = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap_err();
+        assert_eq!(
+            error,
+            CompilerError::UnknownAngleUnit(String::from("gradian"))
+        );
+    }
+
+    #[test]
+    fn parse_version_indicators() {
+        let doc =
+            roxmltree::Document::parse(r#"<compiler coordinate="global" autolimits="true"/>"#)
+                .unwrap();
+        let compiler = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap();
+        assert_eq!(compiler.coordinate, Coordinate::Global);
+        assert_eq!(compiler.autolimits, Some(true));
+
+        let doc = roxmltree::Document::parse(r#"<compiler coordinate="world"/>"#).unwrap();
+        let error = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap_err();
+        assert_eq!(
+            error,
+            CompilerError::UnknownCoordinate(String::from("world"))
+        );
+    }
+
+    #[test]
+    fn parse_mass_and_inertia_bounds() {
+        let doc = roxmltree::Document::parse(r#"<compiler boundmass="0.1" boundinertia="0.01"/>"#)
+            .unwrap();
+        let compiler = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap();
+        assert_eq!(compiler.boundmass, 0.1);
+        assert_eq!(compiler.boundinertia, 0.01);
+
+        let doc = roxmltree::Document::parse(r#"<compiler boundmass="-1"/>"#).unwrap();
+        let error = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap_err();
+        assert_eq!(error, CompilerError::NegativeBound("boundmass"));
+    }
+
+    #[test]
+    fn parse_settotalmass() {
+        let doc = roxmltree::Document::parse(r#"<compiler settotalmass="10"/>"#).unwrap();
+        let compiler = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap();
+        assert_eq!(compiler.settotalmass, Some(10.0));
+
+        let doc = roxmltree::Document::parse(r#"<compiler settotalmass="0"/>"#).unwrap();
+        let error = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap_err();
+        assert_eq!(error, CompilerError::NonPositiveTotalMass);
+    }
+
+    #[test]
+    fn parse_fitscale() {
+        let doc = roxmltree::Document::parse(r#"<compiler fitscale="1.5"/>"#).unwrap();
+        let compiler = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap();
+        assert_eq!(compiler.fitscale, 1.5);
+
+        let doc = roxmltree::Document::parse(r#"<compiler fitscale="-1"/>"#).unwrap();
+        let error = parse_compiler_node::<f64>(&logger(), &doc.root_element()).unwrap_err();
+        assert_eq!(error, CompilerError::NonPositiveFitScale);
+    }
+}
@@ -0,0 +1,257 @@
+use super::geom;
+use super::{element_path, warn_unsupported_attributes};
+use crate::attributes::AttributeSource;
+use crate::parse_options::ParseOptions;
+use roxmltree;
+use slog;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Name of the class defined by the top level `default` element. It is
+/// the ancestor of every other class.
+pub const MAIN_CLASS: &str = "main";
+
+/// Attribute values a default class provides to its elements.
+///
+/// Values are kept as written and parsed together with the element they
+/// apply to, so that they are validated exactly like explicit attributes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DefaultClass {
+    /// `None` only for the main class.
+    pub parent: Option<String>,
+    pub geom: HashMap<String, String>,
+}
+
+/// All default classes of a model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Defaults {
+    classes: HashMap<String, DefaultClass>,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        let mut classes = HashMap::new();
+        classes.insert(MAIN_CLASS.to_string(), DefaultClass::default());
+        Defaults { classes }
+    }
+}
+
+impl Defaults {
+    pub fn get_class(&self, class: &str) -> Option<&DefaultClass> {
+        self.classes.get(class)
+    }
+
+    /// Geom attributes of `class`, including those inherited from its
+    /// ancestors. Values of a class override those of its ancestors.
+    pub fn geom_attributes(&self, class: &str) -> Option<HashMap<String, String>> {
+        let default_class = self.classes.get(class)?;
+        let mut attributes = match &default_class.parent {
+            Some(parent) => self.geom_attributes(parent)?,
+            None => HashMap::new(),
+        };
+        attributes.extend(
+            default_class
+                .geom
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+        Some(attributes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultError {
+    MissingClass,
+    DuplicateClass(String),
+    /// Path of an element the parser does not know.
+    UnknownElement(String),
+}
+
+impl fmt::Display for DefaultError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DefaultError::MissingClass => write!(f, "nested default must have a class"),
+            DefaultError::DuplicateClass(class) => {
+                write!(f, "default class '{}' is defined more than once", class)
+            }
+            DefaultError::UnknownElement(path) => write!(f, "unknown element '{}'", path),
+        }
+    }
+}
+
+impl Error for DefaultError {}
+
+/// Parse the top level `default` element and all of its nested classes.
+pub fn parse_default_node(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    node: &roxmltree::Node,
+    defaults: &mut Defaults,
+) -> Result<(), DefaultError> {
+    parse_class(logger, options, node, MAIN_CLASS, defaults)
+}
+
+fn parse_class(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    node: &roxmltree::Node,
+    class: &str,
+    defaults: &mut Defaults,
+) -> Result<(), DefaultError> {
+    warn_unsupported_attributes(logger, node, &["class"]);
+
+    for child in node.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "default" => {
+                let child_class = child.attribute("class").ok_or(DefaultError::MissingClass)?;
+                if defaults.classes.contains_key(child_class) {
+                    return Err(DefaultError::DuplicateClass(child_class.to_string()));
+                }
+                defaults.classes.insert(
+                    child_class.to_string(),
+                    DefaultClass {
+                        parent: Some(class.to_string()),
+                        ..DefaultClass::default()
+                    },
+                );
+                parse_class(logger, options, &child, child_class, defaults)?;
+            }
+            "geom" => {
+                warn_unsupported_attributes(logger, &child, geom::SUPPORTED_ATTRIBUTES);
+                let default_class = defaults
+                    .classes
+                    .get_mut(class)
+                    .expect("Class must be inserted before it is parsed");
+                // Names identify a single element, so they are never
+                // inherited
+                default_class.geom.extend(
+                    child
+                        .attributes()
+                        .iter()
+                        .filter(|attribute| attribute.name() != "name")
+                        .map(|attribute| {
+                            (attribute.name().to_string(), attribute.value().to_string())
+                        }),
+                );
+            }
+            _ if options.strict_unknown_elements => {
+                return Err(DefaultError::UnknownElement(element_path(&child)));
+            }
+            tag_name => {
+                warn!(logger, "Unsupported default element. Ignoring"; "element" => tag_name)
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// An XML node whose missing attributes are taken from a default class.
+pub struct DefaultedNode<'a, 'input> {
+    node: &'a roxmltree::Node<'a, 'input>,
+    defaults: HashMap<String, String>,
+    ignore_case: bool,
+}
+
+impl<'a, 'input> DefaultedNode<'a, 'input> {
+    pub fn new(node: &'a roxmltree::Node<'a, 'input>, defaults: HashMap<String, String>) -> Self {
+        DefaultedNode {
+            node,
+            defaults,
+            ignore_case: false,
+        }
+    }
+
+    /// Also match attributes of the node whose names only differ in
+    /// case, e.g. `Type` for `type`. Exact matches take precedence.
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    pub fn node(&self) -> &roxmltree::Node<'a, 'input> {
+        self.node
+    }
+
+    /// Attribute given by the node itself rather than by a default
+    /// class, matched like `attribute`.
+    pub fn explicit_attribute(&self, name: &str) -> Option<&str> {
+        self.node.attribute(name).or_else(|| {
+            if !self.ignore_case {
+                return None;
+            }
+            self.node
+                .attributes()
+                .iter()
+                .find(|attribute| attribute.name().eq_ignore_ascii_case(name))
+                .map(|attribute| attribute.value())
+        })
+    }
+}
+
+impl<'a, 'input> AttributeSource for DefaultedNode<'a, 'input> {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.explicit_attribute(name)
+            .or_else(|| self.defaults.get(name).map(String::as_str))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    #[test]
+    fn inherit_nested_class_attributes() {
+        let doc = roxmltree::Document::parse(
+            r#"<default>
+                 <geom type="box" size="1 1 1"/>
+                 <default class="small">
+                   <geom size="0.1 0.1 0.1" rgba="1 0 0 1"/>
+                 </default>
+               </default>"#,
+        )
+        .unwrap();
+        let mut defaults = Defaults::default();
+        parse_default_node(
+            &logger(),
+            &ParseOptions::default(),
+            &doc.root_element(),
+            &mut defaults,
+        )
+        .unwrap();
+
+        let attributes = defaults.geom_attributes("small").unwrap();
+        assert_eq!(attributes["type"], "box");
+        assert_eq!(attributes["size"], "0.1 0.1 0.1");
+        assert_eq!(attributes["rgba"], "1 0 0 1");
+        assert_eq!(
+            defaults.geom_attributes(MAIN_CLASS).unwrap()["size"],
+            "1 1 1"
+        );
+        assert!(defaults.geom_attributes("large").is_none());
+    }
+
+    #[test]
+    fn reject_duplicate_class() {
+        let doc = roxmltree::Document::parse(
+            r#"<default>
+                 <default class="a"/>
+                 <default class="a"/>
+               </default>"#,
+        )
+        .unwrap();
+        let error = parse_default_node(
+            &logger(),
+            &ParseOptions::default(),
+            &doc.root_element(),
+            &mut Defaults::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error, DefaultError::DuplicateClass(String::from("a")));
+    }
+}
@@ -0,0 +1,1734 @@
+use super::default::DefaultedNode;
+use super::warn_unused_attributes;
+use crate::attributes::{
+    self, parse_real_attribute, parse_real_vector_attribute_with_options, Attr, AttrError,
+    AttributeSource, ParseBoolAttributeError, ParseOrientationError, ParseRealVectorError,
+};
+use crate::parse_options::ParseOptions;
+use crate::tags::compiler::CompilerOptions;
+use crate::tags::mesh::MeshAsset;
+use na::allocator::Allocator;
+use na::{
+    DefaultAllocator, DimName, Isometry3, Point3, Point4, Real, Translation3, Unit, UnitQuaternion,
+    Vector3, Vector6, VectorN, U2, U3, U4, U5, U6,
+};
+use nalgebra as na;
+use ncollide3d::shape::{Ball, Capsule, ConvexHull, Cuboid, Cylinder, Plane, ShapeHandle};
+use nphysics3d::object::ColliderDesc;
+use roxmltree;
+use slog;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::num::{ParseFloatError, ParseIntError};
+
+pub(crate) const SUPPORTED_ATTRIBUTES: &[&str] = &[
+    "name",
+    "class",
+    "type",
+    "size",
+    "pos",
+    "quat",
+    "axisangle",
+    "euler",
+    "xyaxes",
+    "zaxis",
+    "fromto",
+    "density",
+    "contype",
+    "conaffinity",
+    "condim",
+    "group",
+    "friction",
+    "solimp",
+    "margin",
+    "gap",
+    "ccd",
+    "priority",
+    "rgba",
+    "material",
+    "user",
+];
+
+/// MuJoCo specific geom properties that have no nphysics equivalent.
+///
+/// These are attached to the parsed `ColliderDesc` as its user data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeomUserData<N: Real> {
+    pub contype: u32,
+    pub conaffinity: u32,
+    pub condim: u32,
+    pub group: i32,
+    pub friction: Vector3<N>,
+    /// Solver impedance as `dmin dmax width midpoint power`.
+    pub solimp: VectorN<N, U5>,
+    /// Distance at which contacts are detected.
+    pub margin: N,
+    /// Contacts closer than `margin` but farther than `margin - gap` are
+    /// detected but not included in the simulation.
+    pub gap: N,
+    /// Enable continuous collision detection for the geom, so that it
+    /// does not tunnel through thin geoms when moving fast. This is not
+    /// a MuJoCo attribute.
+    pub ccd: bool,
+    /// In a contact between geoms of different priority, the contact
+    /// parameters of the geom with the higher priority are used.
+    pub priority: i32,
+    pub rgba: Point4<N>,
+    /// Whether the alpha of `rgba` is below one, so that renderers need
+    /// to blend the geom.
+    pub transparent: bool,
+    /// Corners of the finite quad a plane geom is rendered as, in the
+    /// frame of its body. `None` for other geoms and for infinite
+    /// planes.
+    pub render_quad: Option<[Point3<N>; 4]>,
+    /// Semi-axes of an ellipsoid geom. ncollide has no ellipsoid, so its
+    /// shape is only a convex hull approximating it. `None` for other
+    /// geoms.
+    pub semi_axes: Option<Vector3<N>>,
+    /// Index of the geom in document order.
+    pub order: usize,
+    /// Comment preceding the geom, if comments are captured.
+    pub doc: Option<String>,
+    /// Custom numbers MuJoCo passes through without interpreting them.
+    pub user: Vec<N>,
+}
+
+impl<N: Real> Default for GeomUserData<N> {
+    fn default() -> Self {
+        GeomUserData {
+            contype: 1,
+            conaffinity: 1,
+            condim: 3,
+            group: 0,
+            friction: Vector3::new(N::one(), na::convert(0.005), na::convert(0.0001)),
+            solimp: VectorN::<N, U5>::from_column_slice(&[
+                na::convert(0.9),
+                na::convert(0.95),
+                na::convert(0.001),
+                na::convert(0.5),
+                na::convert(2.0),
+            ]),
+            margin: N::zero(),
+            gap: N::zero(),
+            ccd: false,
+            priority: 0,
+            rgba: Point4::new(
+                na::convert(0.5),
+                na::convert(0.5),
+                na::convert(0.5),
+                N::one(),
+            ),
+            transparent: false,
+            render_quad: None,
+            semi_axes: None,
+            order: 0,
+            doc: None,
+            user: vec![],
+        }
+    }
+}
+
+impl<N: Real> GeomUserData<N> {
+    /// Retrieve the geom data attached to a collider by `parse_geom_node`.
+    pub fn from_collider_desc(collider: &ColliderDesc<N>) -> Option<&GeomUserData<N>> {
+        collider
+            .get_user_data()
+            .and_then(|user_data| user_data.as_any().downcast_ref())
+    }
+
+    /// Friction of a contact between this geom and `other`.
+    ///
+    /// Like in MuJoCo, the friction of the geom with the higher priority
+    /// is used. Geoms of equal priority use the maximum of each friction
+    /// component.
+    pub fn mixed_friction(&self, other: &GeomUserData<N>) -> Vector3<N> {
+        if self.priority > other.priority {
+            self.friction
+        } else if self.priority < other.priority {
+            other.friction
+        } else {
+            self.friction
+                .zip_map(&other.friction, |a, b| if a > b { a } else { b })
+        }
+    }
+
+    /// Whether the geom can collide with any geom at all.
+    ///
+    /// MuJoCo only checks a pair of geoms for contacts if the contype of
+    /// one shares a bit with the conaffinity of the other, so a geom
+    /// with both masks empty is purely visual.
+    pub fn is_collidable(&self) -> bool {
+        self.contype != 0 || self.conaffinity != 0
+    }
+}
+
+/// Kind of the collision shape built for a geom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShapeKind {
+    Ball,
+    Box,
+    Capsule,
+    Cylinder,
+    Ellipsoid,
+    Plane,
+}
+
+impl ShapeKind {
+    /// Kind of a shape built by `parse_geom_node`. Returns `None` for
+    /// other shapes. Ellipsoids can only be told apart from other convex
+    /// hulls by their geom properties, so use `of_collider` for them.
+    pub fn of<N: Real>(shape: &ShapeHandle<N>) -> Option<ShapeKind> {
+        if shape.is_shape::<Ball<N>>() {
+            Some(ShapeKind::Ball)
+        } else if shape.is_shape::<Cuboid<N>>() {
+            Some(ShapeKind::Box)
+        } else if shape.is_shape::<Capsule<N>>() {
+            Some(ShapeKind::Capsule)
+        } else if shape.is_shape::<Cylinder<N>>() {
+            Some(ShapeKind::Cylinder)
+        } else if shape.is_shape::<Plane<N>>() {
+            Some(ShapeKind::Plane)
+        } else {
+            None
+        }
+    }
+
+    /// Kind of the shape of a collider built by `parse_geom_node`,
+    /// including ellipsoids.
+    pub fn of_collider<N: Real>(collider: &ColliderDesc<N>) -> Option<ShapeKind> {
+        if ellipsoid_semi_axes(collider).is_some() {
+            Some(ShapeKind::Ellipsoid)
+        } else {
+            ShapeKind::of(collider.get_shape())
+        }
+    }
+}
+
+/// Semi-axes of the ellipsoid a collider approximates, or `None` if it
+/// is not an ellipsoid.
+pub fn ellipsoid_semi_axes<N: Real>(collider: &ColliderDesc<N>) -> Option<Vector3<N>> {
+    if !collider.get_shape().is_shape::<ConvexHull<N>>() {
+        return None;
+    }
+    GeomUserData::from_collider_desc(collider)?.semi_axes
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeomError {
+    UnknownType(String),
+    UnsupportedType(String),
+    RequiredAttributeMissing(&'static str),
+    InvalidReal {
+        attribute: &'static str,
+        error: ParseFloatError,
+    },
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidInteger {
+        attribute: &'static str,
+        error: ParseIntError,
+    },
+    InvalidBool {
+        attribute: &'static str,
+        error: ParseBoolAttributeError,
+    },
+    InvalidOrientation(ParseOrientationError),
+    InvalidCondim(u32),
+    FromToWithPos,
+    /// fromto determines the orientation, so it cannot be combined with
+    /// an orientation attribute.
+    OrientationWithFromTo(&'static str),
+    BadFromTo(String),
+    FromToUnsupported(String),
+    DegenerateFromTo,
+    CapsuleNeedsFromToOrHalfLength,
+    /// A cylinder given by fromto also has a half length in its size.
+    HalfLengthWithFromTo,
+    WrongSizeArity {
+        attribute: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    UnexpectedChildElement(String),
+    /// An attribute whose name only differs from a MuJoCo attribute in
+    /// case. Only an error in strict mode.
+    MisCasedAttribute(String),
+    NonPositiveSize {
+        component: &'static str,
+    },
+    GapExceedsMargin,
+}
+
+impl fmt::Display for GeomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeomError::UnknownType(geom_type) => write!(f, "unknown geom type '{}'", geom_type),
+            GeomError::UnsupportedType(geom_type) => {
+                write!(f, "geom type '{}' is not supported yet", geom_type)
+            }
+            GeomError::RequiredAttributeMissing(attribute) => {
+                write!(f, "geom is missing required attribute '{}'", attribute)
+            }
+            GeomError::InvalidReal { attribute, error } => {
+                write!(f, "invalid geom {}: {}", attribute, error)
+            }
+            GeomError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid geom {}: {}", attribute, error)
+            }
+            GeomError::InvalidInteger { attribute, error } => {
+                write!(f, "invalid geom {}: {}", attribute, error)
+            }
+            GeomError::InvalidBool { attribute, error } => {
+                write!(f, "invalid geom {}: {}", attribute, error)
+            }
+            GeomError::InvalidOrientation(error) => {
+                write!(f, "invalid geom orientation: {}", error)
+            }
+            GeomError::InvalidCondim(condim) => write!(
+                f,
+                "invalid geom condim {}. Expected one of 1, 3, 4 or 6",
+                condim
+            ),
+            GeomError::FromToWithPos => {
+                write!(f, "geom cannot specify both fromto and pos")
+            }
+            GeomError::OrientationWithFromTo(attribute) => {
+                write!(f, "geom cannot specify both fromto and {}", attribute)
+            }
+            GeomError::BadFromTo(fromto) => write!(
+                f,
+                "geom fromto must be six finite numbers, but found '{}'",
+                fromto
+            ),
+            GeomError::FromToUnsupported(geom_type) => {
+                write!(f, "fromto is not supported for geom type '{}'", geom_type)
+            }
+            GeomError::DegenerateFromTo => {
+                write!(f, "geom fromto start and end points coincide")
+            }
+            GeomError::CapsuleNeedsFromToOrHalfLength => write!(
+                f,
+                "capsule size only specifies the radius. Either add the half length as the \
+                 second size element or specify fromto"
+            ),
+            GeomError::HalfLengthWithFromTo => write!(
+                f,
+                "geom fromto specifies the length, so size must only contain the radius"
+            ),
+            GeomError::WrongSizeArity {
+                attribute,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "geom {} must have {} components, but found {}",
+                attribute, expected, actual
+            ),
+            GeomError::UnexpectedChildElement(tag_name) => {
+                write!(f, "geom cannot contain elements, but found '{}'", tag_name)
+            }
+            GeomError::MisCasedAttribute(name) => write!(
+                f,
+                "geom attribute '{}' must be written as '{}'",
+                name,
+                name.to_ascii_lowercase()
+            ),
+            GeomError::NonPositiveSize { component } => {
+                write!(f, "geom size {} must be positive", component)
+            }
+            GeomError::GapExceedsMargin => write!(f, "geom gap cannot be larger than its margin"),
+        }
+    }
+}
+
+impl Error for GeomError {}
+
+impl From<AttrError> for GeomError {
+    fn from(error: AttrError) -> Self {
+        match error {
+            AttrError::InvalidReal { attribute, error } => {
+                GeomError::InvalidReal { attribute, error }
+            }
+            AttrError::InvalidRealVector { attribute, error } => {
+                GeomError::InvalidRealVector { attribute, error }
+            }
+            AttrError::InvalidBool { attribute, error } => {
+                GeomError::InvalidBool { attribute, error }
+            }
+            AttrError::InvalidInteger { attribute, error } => {
+                GeomError::InvalidInteger { attribute, error }
+            }
+        }
+    }
+}
+
+impl From<ParseOrientationError> for GeomError {
+    fn from(error: ParseOrientationError) -> Self {
+        GeomError::InvalidOrientation(error)
+    }
+}
+
+/// Collider of a sphere geom with MuJoCo's default density and geom
+/// properties, for building models in code.
+pub fn make_ball<N: Real>(radius: N) -> ColliderDesc<N> {
+    geom_collider(ShapeHandle::new(Ball::new(radius)))
+}
+
+/// Collider of a box geom with MuJoCo's default density and geom
+/// properties, for building models in code.
+pub fn make_box<N: Real>(half_extents: Vector3<N>) -> ColliderDesc<N> {
+    geom_collider(ShapeHandle::new(Cuboid::new(half_extents)))
+}
+
+/// Collider of a capsule geom with MuJoCo's default density and geom
+/// properties, for building models in code. Like in MuJoCo, the capsule
+/// is aligned with the Z axis.
+pub fn make_capsule<N: Real>(half_length: N, radius: N) -> ColliderDesc<N> {
+    geom_collider(ShapeHandle::new(Capsule::new(half_length, radius))).position(
+        Isometry3::from_parts(Translation3::identity(), y_axis_to_z_axis()),
+    )
+}
+
+/// Collider of a cylinder geom with MuJoCo's default density and geom
+/// properties, for building models in code. Like in MuJoCo, the cylinder
+/// is aligned with the Z axis.
+pub fn make_cylinder<N: Real>(half_length: N, radius: N) -> ColliderDesc<N> {
+    geom_collider(ShapeHandle::new(Cylinder::new(half_length, radius))).position(
+        Isometry3::from_parts(Translation3::identity(), y_axis_to_z_axis()),
+    )
+}
+
+/// Number of points sampled around the Z axis of an ellipsoid. Half as
+/// many rings are sampled from pole to pole. Both are even, so the hull
+/// reaches the ellipsoid at the end of every semi-axis.
+const ELLIPSOID_SUBDIVISIONS: usize = 16;
+
+/// Collider of an ellipsoid geom with MuJoCo's default density and geom
+/// properties, for building models in code. ncollide has no ellipsoid,
+/// so it is approximated by the convex hull of points sampled on its
+/// surface.
+///
+/// # Panics
+///
+/// Panics if a semi-axis is not positive.
+pub fn make_ellipsoid<N: Real>(semi_axes: Vector3<N>) -> ColliderDesc<N> {
+    assert!(
+        semi_axes.iter().all(|&radius| radius > N::zero()),
+        "ellipsoid semi-axes must be positive"
+    );
+
+    let rings = ELLIPSOID_SUBDIVISIONS / 2;
+    let mut points = Vec::with_capacity(ELLIPSOID_SUBDIVISIONS * (rings - 1) + 2);
+    points.push(Point3::new(N::zero(), N::zero(), semi_axes.z));
+    points.push(Point3::new(N::zero(), N::zero(), -semi_axes.z));
+    for ring in 1..rings {
+        let polar = N::pi() * na::convert(ring as f64 / rings as f64);
+        for step in 0..ELLIPSOID_SUBDIVISIONS {
+            let azimuth = N::two_pi() * na::convert(step as f64 / ELLIPSOID_SUBDIVISIONS as f64);
+            points.push(Point3::new(
+                semi_axes.x * polar.sin() * azimuth.cos(),
+                semi_axes.y * polar.sin() * azimuth.sin(),
+                semi_axes.z * polar.cos(),
+            ));
+        }
+    }
+
+    let hull = ConvexHull::try_from_points(&points)
+        .expect("Points sampled on an ellipsoid must have a convex hull");
+    geom_collider(ShapeHandle::new(hull)).user_data(GeomUserData {
+        semi_axes: Some(semi_axes),
+        ..GeomUserData::default()
+    })
+}
+
+fn geom_collider<N: Real>(shape: ShapeHandle<N>) -> ColliderDesc<N> {
+    ColliderDesc::new(shape)
+        .density(na::convert(1000.0))
+        .user_data(GeomUserData::<N>::default())
+}
+
+pub fn parse_geom_node<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &roxmltree::Node,
+) -> Result<ColliderDesc<N>, GeomError> {
+    parse_geom_node_with_defaults(
+        logger,
+        options,
+        compiler,
+        node,
+        HashMap::new(),
+        &HashMap::new(),
+    )
+}
+
+/// Parse a geom, taking every attribute the geom does not specify from
+/// `defaults`. Primitive geoms that reference one of `meshes` are fitted
+/// to it instead of using their size.
+///
+/// A referenced mesh that is not in `meshes` is ignored, so the size is
+/// used as if no mesh was given.
+pub fn parse_geom_node_with_defaults<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &roxmltree::Node,
+    defaults: HashMap<String, String>,
+    meshes: &HashMap<String, MeshAsset<N>>,
+) -> Result<ColliderDesc<N>, GeomError> {
+    warn_unused_attributes(logger, node, SUPPORTED_ATTRIBUTES, !options.strict);
+
+    // MuJoCo does not allow any elements inside of a geom, so children
+    // indicate a malformed model
+    for child in node.children().filter(|child| child.is_element()) {
+        let tag_name = child.tag_name().name();
+        if options.strict {
+            return Err(GeomError::UnexpectedChildElement(tag_name.to_string()));
+        }
+        warn!(logger, "Geom contains an unexpected element. Ignoring"; "element" => tag_name);
+    }
+
+    // Hand written models sometimes use the wrong case, e.g.
+    // `Type="Sphere"`, which is only accepted outside of strict mode
+    if options.strict {
+        for attribute in node.attributes() {
+            let name = attribute.name();
+            let is_miscased = !SUPPORTED_ATTRIBUTES.contains(&name)
+                && SUPPORTED_ATTRIBUTES
+                    .iter()
+                    .any(|supported| supported.eq_ignore_ascii_case(name));
+            if is_miscased {
+                return Err(GeomError::MisCasedAttribute(name.to_string()));
+            }
+        }
+    }
+    let node = &DefaultedNode::new(node, defaults).ignore_case(!options.strict);
+    let geom_type = if options.strict {
+        default_geom_type(node).to_string()
+    } else {
+        default_geom_type(node).to_ascii_lowercase()
+    };
+    let scale: N = na::convert(options.length_scale);
+
+    let fromto = match node.attribute("fromto") {
+        Some(fromto) => {
+            // fromto overrides a position inherited from a default class
+            if node.explicit_attribute("pos").is_some() {
+                return Err(GeomError::FromToWithPos);
+            }
+            let orientation = attributes::ORIENTATION_ATTRIBUTES
+                .iter()
+                .cloned()
+                .find(|attribute| node.explicit_attribute(attribute).is_some());
+            if let Some(attribute) = orientation {
+                return Err(GeomError::OrientationWithFromTo(attribute));
+            }
+            Some(parse_fromto(options, fromto)? * scale)
+        }
+        None => None,
+    };
+
+    let mut render_quad = None;
+    let mut semi_axes = None;
+    let (collider, position) = match (geom_type.as_str(), fromto) {
+        ("sphere", None) => {
+            let mesh = node.attribute("mesh").and_then(|mesh| meshes.get(mesh));
+            let radius = match mesh {
+                Some(mesh) => {
+                    let radius = mesh.bounding_radius() * compiler.fitscale * scale;
+                    if radius <= N::zero() {
+                        return Err(GeomError::NonPositiveSize {
+                            component: "radius",
+                        });
+                    }
+                    radius
+                }
+                None => parse_sphere_radius(logger, options, node, scale)?,
+            };
+            (
+                make_ball(radius),
+                parse_frame(options, compiler, node, scale)?,
+            )
+        }
+        ("capsule", None) => {
+            // Without fromto a lone radius leaves the capsule length
+            // undefined
+            if let Some(size) = node.attribute("size") {
+                if size_values(options, size)?.split_whitespace().count() == 1 {
+                    return Err(GeomError::CapsuleNeedsFromToOrHalfLength);
+                }
+            }
+            let size = parse_size_vector::<N, U2>(options, node, scale)?;
+            let frame = parse_frame(options, compiler, node, scale)?;
+            (make_capsule(size[1], size[0]), frame * y_axis_to_z_axis())
+        }
+        ("capsule", Some(fromto)) => {
+            // fromto gives the length, so like in MuJoCo a half length,
+            // e.g. from a default class, is ignored
+            let radius = match parse_real_list::<N>(options, node, "size")? {
+                Some(ref size) if !size.is_empty() => size[0] * scale,
+                _ => return Err(GeomError::RequiredAttributeMissing("size")),
+            };
+            if radius <= N::zero() {
+                return Err(GeomError::NonPositiveSize {
+                    component: "radius",
+                });
+            }
+            let (half_length, position) = fromto_frame(options, &fromto)?;
+            (make_capsule(half_length, radius), position)
+        }
+        ("box", None) => {
+            let half_extents = parse_size_vector::<N, U3>(options, node, scale)?;
+            for (&half_extent, &component) in
+                half_extents
+                    .iter()
+                    .zip(&["x half-extent", "y half-extent", "z half-extent"])
+            {
+                if half_extent <= N::zero() {
+                    return Err(GeomError::NonPositiveSize { component });
+                }
+            }
+            (
+                make_box(half_extents),
+                parse_frame(options, compiler, node, scale)?,
+            )
+        }
+        // The plane size only affects rendering in MuJoCo, so it is
+        // not needed to build the collision shape. The normal is rotated
+        // by the collider position.
+        ("plane", None) => {
+            let frame = parse_frame(options, compiler, node, scale)?;
+            if node.has_attribute("size") {
+                let size = parse_size_vector::<N, U3>(options, node, scale)?;
+                render_quad = plane_render_quad(&frame, size[0], size[1]);
+            }
+            (
+                geom_collider(ShapeHandle::new(Plane::new(Vector3::z_axis()))),
+                frame,
+            )
+        }
+        ("box", Some(fromto)) => {
+            // fromto gives the length, so only the half extents across
+            // the segment are taken from the size
+            let size = parse_real_list::<N>(options, node, "size")?
+                .ok_or(GeomError::RequiredAttributeMissing("size"))?;
+            if size.len() < 2 {
+                return Err(GeomError::WrongSizeArity {
+                    attribute: "size",
+                    expected: 2,
+                    actual: size.len(),
+                });
+            }
+            for (&half_extent, &component) in size.iter().zip(&["x half-extent", "y half-extent"]) {
+                if half_extent <= N::zero() {
+                    return Err(GeomError::NonPositiveSize { component });
+                }
+            }
+            let (half_length, position) = fromto_frame_along(options, &fromto, &Vector3::z_axis())?;
+            (
+                make_box(Vector3::new(size[0] * scale, size[1] * scale, half_length)),
+                position,
+            )
+        }
+        ("ellipsoid", None) => {
+            // A missing semi-axis cannot be filled in like a missing
+            // half length, so the size must give all three
+            let num_semi_axes =
+                parse_real_list::<N>(options, node, "size")?.map_or(0, |size| size.len());
+            if num_semi_axes < 3 {
+                return Err(GeomError::RequiredAttributeMissing("size"));
+            }
+            let size = parse_size_vector::<N, U3>(options, node, scale)?;
+            for (&radius, &component) in
+                size.iter()
+                    .zip(&["x semi-axis", "y semi-axis", "z semi-axis"])
+            {
+                if radius <= N::zero() {
+                    return Err(GeomError::NonPositiveSize { component });
+                }
+            }
+            semi_axes = Some(size);
+            (
+                make_ellipsoid(size),
+                parse_frame(options, compiler, node, scale)?,
+            )
+        }
+        ("sphere", Some(_)) | ("plane", Some(_)) | ("ellipsoid", Some(_)) => {
+            return Err(GeomError::FromToUnsupported(geom_type.to_string()));
+        }
+        ("cylinder", None) => {
+            let size = parse_size_vector::<N, U2>(options, node, scale)?;
+            for (&value, &component) in size.iter().zip(&["radius", "half length"]) {
+                if value <= N::zero() {
+                    return Err(GeomError::NonPositiveSize { component });
+                }
+            }
+            let frame = parse_frame(options, compiler, node, scale)?;
+            (make_cylinder(size[1], size[0]), frame * y_axis_to_z_axis())
+        }
+        ("cylinder", Some(fromto)) => {
+            // Unlike for capsules, an explicit half length next to fromto
+            // is rejected. One inherited from a default class is ignored.
+            if let Some(size) = node.explicit_attribute("size") {
+                if size_values(options, size)?.split_whitespace().count() > 1 {
+                    return Err(GeomError::HalfLengthWithFromTo);
+                }
+            }
+            let radius = match parse_real_list::<N>(options, node, "size")? {
+                Some(ref size) if !size.is_empty() => size[0] * scale,
+                _ => return Err(GeomError::RequiredAttributeMissing("size")),
+            };
+            if radius <= N::zero() {
+                return Err(GeomError::NonPositiveSize {
+                    component: "radius",
+                });
+            }
+            let (half_length, position) = fromto_frame(options, &fromto)?;
+            (make_cylinder(half_length, radius), position)
+        }
+        ("mesh", _) | ("hfield", _) => {
+            return Err(GeomError::UnsupportedType(geom_type.to_string()));
+        }
+        (_, _) => return Err(GeomError::UnknownType(geom_type.to_string())),
+    };
+
+    let density = Attr::real(node, "density")?.unwrap_or_else(|| na::convert(1000.0));
+
+    let mut user_data = parse_user_data(options, node, scale)?;
+    user_data.render_quad = render_quad;
+    user_data.semi_axes = semi_axes;
+
+    let mut collider = collider
+        .position(position)
+        .density(density)
+        .user_data(user_data);
+    if let Some(name) = node.node().attribute("name") {
+        collider = collider.name(name.to_string());
+    }
+
+    Ok(collider)
+}
+
+/// Corners of a plane with the given half extents in the frame the
+/// plane is positioned in. MuJoCo renders planes with a zero half extent
+/// as infinite, so they have no quad.
+fn plane_render_quad<N: Real>(
+    frame: &Isometry3<N>,
+    half_x: N,
+    half_y: N,
+) -> Option<[Point3<N>; 4]> {
+    if half_x <= N::zero() || half_y <= N::zero() {
+        return None;
+    }
+    let corner = |x, y| frame * Point3::new(x, y, N::zero());
+    Some([
+        corner(-half_x, -half_y),
+        corner(half_x, -half_y),
+        corner(half_x, half_y),
+        corner(-half_x, half_y),
+    ])
+}
+
+/// ncollide's capsules are aligned with the Y axis, while MuJoCo's are
+/// aligned with the Z axis.
+pub(crate) fn y_axis_to_z_axis<N: Real>() -> UnitQuaternion<N> {
+    UnitQuaternion::from_axis_angle(&Vector3::x_axis(), N::frac_pi_2())
+}
+
+/// Type of a geom. Like MuJoCo, a geom referencing a mesh asset is a
+/// mesh unless its type is given explicitly.
+pub(crate) fn default_geom_type<'a>(node: &'a dyn AttributeSource) -> &'a str {
+    match node.attribute("type") {
+        Some(geom_type) => geom_type,
+        None if node.has_attribute("mesh") => "mesh",
+        None => "sphere",
+    }
+}
+
+fn parse_frame<N: Real>(
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &dyn AttributeSource,
+    scale: N,
+) -> Result<Isometry3<N>, GeomError> {
+    let translation = match node.attribute("pos") {
+        Some(pos) => {
+            parse_real_vector_attribute_with_options::<N, U3>(options, pos)
+                .map_err(|error| fixed_size_vector_error("pos", error))?
+                * scale
+        }
+        None => Vector3::zeros(),
+    };
+    let rotation = attributes::parse_orientation_attribute(options, compiler, node)?;
+
+    Ok(Isometry3::from_parts(
+        Translation3::new(translation.x, translation.y, translation.z),
+        rotation,
+    ))
+}
+
+fn parse_fromto<N: Real>(options: &ParseOptions, fromto: &str) -> Result<Vector6<N>, GeomError> {
+    let bad_fromto = || GeomError::BadFromTo(fromto.to_string());
+    let values = parse_real_vector_attribute_with_options::<f64, U6>(options, fromto)
+        .map_err(|_| bad_fromto())?;
+    if values.iter().any(|value| !value.is_finite()) {
+        return Err(bad_fromto());
+    }
+    Ok(values.map(na::convert))
+}
+
+/// Compute the half length and the frame centered on the fromto segment
+/// with the local Y axis pointing from the start to the end point.
+fn fromto_frame<N: Real>(
+    options: &ParseOptions,
+    fromto: &Vector6<N>,
+) -> Result<(N, Isometry3<N>), GeomError> {
+    fromto_frame_along(options, fromto, &Vector3::y_axis())
+}
+
+/// Compute the half length and the frame centered on the fromto segment
+/// with `local_axis` pointing from the start to the end point.
+///
+/// The rotation about the segment is not determined by fromto. The frame
+/// uses the smallest rotation that aligns `local_axis` with the segment,
+/// so the result only depends on the segment direction. A segment
+/// pointing opposite to `local_axis` is reached by a half turn about the
+/// local X axis.
+fn fromto_frame_along<N: Real>(
+    options: &ParseOptions,
+    fromto: &Vector6<N>,
+    local_axis: &Unit<Vector3<N>>,
+) -> Result<(N, Isometry3<N>), GeomError> {
+    let from = Vector3::new(fromto[0], fromto[1], fromto[2]);
+    let to = Vector3::new(fromto[3], fromto[4], fromto[5]);
+    let axis = to - from;
+    let length = axis.norm();
+    if length <= options.degenerate_epsilon() {
+        return Err(GeomError::DegenerateFromTo);
+    }
+
+    let half: N = na::convert(0.5);
+    let center = (from + to) * half;
+    // rotation_between is undefined when the axis points against the
+    // local axis. Both supported local axes are orthogonal to X.
+    let rotation = UnitQuaternion::rotation_between(local_axis.as_ref(), &axis)
+        .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::x_axis(), N::pi()));
+
+    Ok((
+        length * half,
+        Isometry3::from_parts(Translation3::new(center.x, center.y, center.z), rotation),
+    ))
+}
+
+fn parse_size_real<N: Real>(size: &str, scale: N) -> Result<N, GeomError> {
+    let size: N = parse_real_attribute(size).map_err(|error| GeomError::InvalidReal {
+        attribute: "size",
+        error,
+    })?;
+    Ok(size * scale)
+}
+
+/// Some exporters pad sphere sizes with zeros to three components. The
+/// padding is ignored outside of strict mode.
+fn parse_sphere_radius<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    node: &dyn AttributeSource,
+    scale: N,
+) -> Result<N, GeomError> {
+    let size = node
+        .attribute("size")
+        .ok_or(GeomError::RequiredAttributeMissing("size"))?;
+    let values = size_values(options, size)?;
+    let components = values.split_whitespace().collect::<Vec<_>>();
+    if components.len() <= 1 {
+        return parse_size_real(components.first().cloned().unwrap_or(""), scale);
+    }
+
+    let wrong_arity = GeomError::WrongSizeArity {
+        attribute: "size",
+        expected: 1,
+        actual: components.len(),
+    };
+    if options.strict {
+        return Err(wrong_arity);
+    }
+    let values = components
+        .iter()
+        .map(|component| parse_real_attribute::<N>(component))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| GeomError::InvalidReal {
+            attribute: "size",
+            error,
+        })?;
+    if values[1..].iter().any(|&value| value != N::zero()) {
+        return Err(wrong_arity);
+    }
+    warn!(logger, "Sphere size is padded with zeros. Ignoring the padding"; "size" => size);
+
+    Ok(values[0] * scale)
+}
+
+fn parse_size_vector<N: Real, D: DimName>(
+    options: &ParseOptions,
+    node: &dyn AttributeSource,
+    scale: N,
+) -> Result<VectorN<N, D>, GeomError>
+where
+    DefaultAllocator: Allocator<N, D>,
+{
+    let size = node
+        .attribute("size")
+        .ok_or(GeomError::RequiredAttributeMissing("size"))?;
+    let size: VectorN<N, D> = parse_real_vector_attribute_with_options(options, size)
+        .map_err(|error| fixed_size_vector_error("size", error))?;
+    Ok(size * scale)
+}
+
+/// Report a wrong number of components separately from malformed reals,
+/// so that the message names the offending attribute and its arity.
+fn fixed_size_vector_error(attribute: &'static str, error: ParseRealVectorError) -> GeomError {
+    match error {
+        ParseRealVectorError::WrongNumberOfElements { expected, actual } => {
+            GeomError::WrongSizeArity {
+                attribute,
+                expected,
+                actual,
+            }
+        }
+        error => GeomError::InvalidRealVector { attribute, error },
+    }
+}
+
+/// The values of a size with commas accepted as separators outside of
+/// strict mode, for counting them.
+fn size_values<'a>(options: &ParseOptions, size: &'a str) -> Result<Cow<'a, str>, GeomError> {
+    attributes::commas_as_whitespace(options, size).map_err(|error| GeomError::InvalidRealVector {
+        attribute: "size",
+        error,
+    })
+}
+
+/// Parse a whitespace separated list of reals of any length. Outside of
+/// strict mode commas are accepted as separators as well.
+fn parse_real_list<N: Real>(
+    options: &ParseOptions,
+    node: &dyn AttributeSource,
+    attribute: &'static str,
+) -> Result<Option<Vec<N>>, GeomError> {
+    let values = match node.attribute(attribute) {
+        Some(values) => attributes::commas_as_whitespace(options, values)
+            .map_err(|error| GeomError::InvalidRealVector { attribute, error })?,
+        None => return Ok(None),
+    };
+    values
+        .split_whitespace()
+        .map(parse_real_attribute)
+        .collect::<Result<Vec<N>, _>>()
+        .map(Some)
+        .map_err(|error| GeomError::InvalidRealVector {
+            attribute,
+            error: error.into(),
+        })
+}
+
+fn parse_user_data<N: Real>(
+    options: &ParseOptions,
+    node: &dyn AttributeSource,
+    scale: N,
+) -> Result<GeomUserData<N>, GeomError> {
+    let mut user_data = GeomUserData::default();
+
+    if let Some(contype) = Attr::int(node, "contype")? {
+        user_data.contype = contype;
+    }
+    if let Some(conaffinity) = Attr::int(node, "conaffinity")? {
+        user_data.conaffinity = conaffinity;
+    }
+    if let Some(condim) = Attr::int(node, "condim")? {
+        match condim {
+            1 | 3 | 4 | 6 => user_data.condim = condim,
+            _ => return Err(GeomError::InvalidCondim(condim)),
+        }
+    }
+    if let Some(group) = Attr::int(node, "group")? {
+        user_data.group = group;
+    }
+    if let Some(priority) = Attr::int(node, "priority")? {
+        user_data.priority = priority;
+    }
+
+    // MuJoCo allows friction to be partially specified. Missing
+    // components keep their default values.
+    if let Some(values) = parse_real_list(options, node, "friction")? {
+        if values.is_empty() || values.len() > 3 {
+            return Err(GeomError::InvalidRealVector {
+                attribute: "friction",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 3,
+                    actual: values.len(),
+                },
+            });
+        }
+        for (i, value) in values.into_iter().enumerate() {
+            user_data.friction[i] = value;
+        }
+    }
+
+    // The short form of solimp leaves out the width and midpoint of the
+    // impedance function
+    if let Some(values) = parse_real_list(options, node, "solimp")? {
+        if values.len() != 3 && values.len() != 5 {
+            return Err(GeomError::InvalidRealVector {
+                attribute: "solimp",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 5,
+                    actual: values.len(),
+                },
+            });
+        }
+        for (i, value) in values.into_iter().enumerate() {
+            user_data.solimp[i] = value;
+        }
+    }
+
+    if let Some(margin) = Attr::real(node, "margin")? {
+        user_data.margin = margin * scale;
+    }
+    if let Some(gap) = Attr::real(node, "gap")? {
+        user_data.gap = gap * scale;
+    }
+    if user_data.gap > user_data.margin {
+        return Err(GeomError::GapExceedsMargin);
+    }
+
+    if let Some(ccd) = Attr::bool(node, "ccd")? {
+        user_data.ccd = ccd;
+    }
+
+    if let Some(user) = parse_real_list(options, node, "user")? {
+        user_data.user = user;
+    }
+
+    if let Some(rgba) = node.attribute("rgba") {
+        user_data.rgba =
+            parse_rgba(options, rgba).map_err(|error| GeomError::InvalidRealVector {
+                attribute: "rgba",
+                error,
+            })?;
+        user_data.transparent = user_data.rgba.w < N::one();
+    }
+
+    Ok(user_data)
+}
+
+/// Parse a color with an optional alpha, which defaults to opaque.
+fn parse_rgba<N: Real>(
+    options: &ParseOptions,
+    text: &str,
+) -> Result<Point4<N>, ParseRealVectorError> {
+    match parse_real_vector_attribute_with_options::<N, U4>(options, text) {
+        Ok(rgba) => Ok(Point4::new(rgba[0], rgba[1], rgba[2], rgba[3])),
+        Err(ParseRealVectorError::WrongNumberOfElements { actual: 3, .. }) => {
+            let rgb = parse_real_vector_attribute_with_options::<N, U3>(options, text)?;
+            Ok(Point4::new(rgb[0], rgb[1], rgb[2], N::one()))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies;
+    use approx::assert_relative_eq;
+    use proptest::prelude::*;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    fn parse_geom(text: &str) -> Result<ColliderDesc<f64>, GeomError> {
+        parse_geom_with_options(text, &ParseOptions::default())
+    }
+
+    fn parse_geom_with_options(
+        text: &str,
+        options: &ParseOptions,
+    ) -> Result<ColliderDesc<f64>, GeomError> {
+        let doc = roxmltree::Document::parse(text).unwrap();
+        parse_geom_node(
+            &logger(),
+            options,
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn parse_sphere_geom(radius in 0.001f64..100.0,
+                             x in -100.0f64..100.0,
+                             y in -100.0f64..100.0,
+                             z in -100.0f64..100.0) {
+            let text = format!(r#"<geom type="sphere" size="{}" pos="{} {} {}"/>"#,
+                               radius, x, y, z);
+            let collider = parse_geom(&text).unwrap();
+
+            let ball = collider.get_shape().as_shape::<Ball<f64>>().unwrap();
+            prop_assert_eq!(ball.radius(), radius);
+            prop_assert_eq!(collider.get_translation(), &Vector3::new(x, y, z));
+        }
+
+        #[test]
+        fn parse_box_geom(x in 0.001f64..100.0,
+                          y in 0.001f64..100.0,
+                          z in 0.001f64..100.0) {
+            let text = format!(r#"<geom type="box" size="{} {} {}"/>"#, x, y, z);
+            let collider = parse_geom(&text).unwrap();
+
+            let cuboid = collider.get_shape().as_shape::<Cuboid<f64>>().unwrap();
+            prop_assert_eq!(cuboid.half_extents(), &Vector3::new(x, y, z));
+        }
+
+        #[test]
+        fn parse_capsule_geom(radius in 0.001f64..100.0,
+                              half_length in 0.001f64..100.0) {
+            let text = format!(r#"<geom type="capsule" size="{} {}"/>"#, radius, half_length);
+            let collider = parse_geom(&text).unwrap();
+
+            let capsule = collider.get_shape().as_shape::<Capsule<f64>>().unwrap();
+            prop_assert_eq!(capsule.radius(), radius);
+            prop_assert_eq!(capsule.half_height(), half_length);
+        }
+
+        #[test]
+        fn parse_cylinder_geom(radius in 0.001f64..100.0,
+                               half_length in 0.001f64..100.0) {
+            let text = format!(r#"<geom type="cylinder" size="{} {}"/>"#, radius, half_length);
+            let collider = parse_geom(&text).unwrap();
+
+            let cylinder = collider.get_shape().as_shape::<Cylinder<f64>>().unwrap();
+            prop_assert_eq!(cylinder.radius(), radius);
+            prop_assert_eq!(cylinder.half_height(), half_length);
+        }
+
+        #[test]
+        fn parse_cylinder_fromto_geom(radius in 0.001f64..100.0,
+                                      x in -100.0f64..100.0,
+                                      y in -100.0f64..100.0,
+                                      z in 0.001f64..100.0) {
+            let text = format!(r#"<geom type="cylinder" size="{}" fromto="{} {} 0 {} {} {}"/>"#,
+                               radius, x, y, x, y, z);
+            let collider = parse_geom(&text).unwrap();
+
+            let cylinder = collider.get_shape().as_shape::<Cylinder<f64>>().unwrap();
+            prop_assert_eq!(cylinder.radius(), radius);
+            prop_assert!((cylinder.half_height() - z / 2.0).abs() < 1e-9);
+            prop_assert!((collider.get_translation() - Vector3::new(x, y, z / 2.0)).norm() < 1e-9);
+            prop_assert!((collider.get_rotation() * Vector3::y() - Vector3::z()).norm() < 1e-9);
+        }
+
+        #[test]
+        fn parse_generated_geom(geom in strategies::any_geom()) {
+            let collider = parse_geom(&geom.xml).unwrap();
+
+            let shape = collider.get_shape();
+            let size = match geom.geom_type {
+                "sphere" => vec![shape.as_shape::<Ball<f64>>().unwrap().radius()],
+                "box" => shape.as_shape::<Cuboid<f64>>().unwrap().half_extents().as_slice().to_vec(),
+                "capsule" => {
+                    let capsule = shape.as_shape::<Capsule<f64>>().unwrap();
+                    vec![capsule.radius(), capsule.half_height()]
+                }
+                "cylinder" => {
+                    let cylinder = shape.as_shape::<Cylinder<f64>>().unwrap();
+                    vec![cylinder.radius(), cylinder.half_height()]
+                }
+                geom_type => panic!("unexpected geom type {}", geom_type),
+            };
+            prop_assert_eq!(size, geom.size);
+            prop_assert_eq!(collider.get_translation(), &Vector3::from(geom.pos));
+        }
+    }
+
+    #[test]
+    fn parse_capsule_fromto_geom() {
+        let collider =
+            parse_geom(r#"<geom type="capsule" size="0.1" fromto="0 0 0 0 0 2"/>"#).unwrap();
+
+        let capsule = collider.get_shape().as_shape::<Capsule<f64>>().unwrap();
+        assert_relative_eq!(capsule.radius(), 0.1);
+        assert_relative_eq!(capsule.half_height(), 1.0);
+        assert_relative_eq!(*collider.get_translation(), Vector3::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(
+            collider.get_rotation() * Vector3::y(),
+            Vector3::z(),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn parse_box_fromto_geom() {
+        let text = r#"<geom type="box" size="0.1 0.2" fromto="0 0 0 1 1 0"/>"#;
+        let collider = parse_geom(text).unwrap();
+
+        let cuboid = collider.get_shape().as_shape::<Cuboid<f64>>().unwrap();
+        assert_relative_eq!(
+            *cuboid.half_extents(),
+            Vector3::new(0.1, 0.2, 0.5_f64.sqrt()),
+            epsilon = 1e-10
+        );
+        assert_relative_eq!(*collider.get_translation(), Vector3::new(0.5, 0.5, 0.0));
+        assert_relative_eq!(
+            collider.get_rotation() * Vector3::z(),
+            Vector3::new(1.0, 1.0, 0.0).normalize(),
+            epsilon = 1e-10
+        );
+        // The roll about the segment is the one of the smallest rotation,
+        // whose axis is orthogonal to both the local Z axis and the segment
+        let axis = collider.get_rotation().axis().unwrap();
+        assert_relative_eq!(axis.dot(&Vector3::z()), 0.0, epsilon = 1e-10);
+        assert_eq!(
+            parse_geom(text).unwrap().get_position(),
+            collider.get_position()
+        );
+
+        // A segment pointing down is reached by a half turn about X
+        let collider =
+            parse_geom(r#"<geom type="box" size="0.1 0.2" fromto="0 0 1 0 0 0"/>"#).unwrap();
+        assert_relative_eq!(
+            collider.get_rotation() * Vector3::x(),
+            Vector3::x(),
+            epsilon = 1e-10
+        );
+        assert_relative_eq!(
+            collider.get_rotation() * Vector3::z(),
+            -Vector3::z(),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn reject_non_positive_capsule_fromto_radius() {
+        let error =
+            parse_geom(r#"<geom type="capsule" size="0" fromto="0 0 0 0 0 1"/>"#).unwrap_err();
+        assert_eq!(
+            error,
+            GeomError::NonPositiveSize {
+                component: "radius"
+            }
+        );
+    }
+
+    #[test]
+    fn orient_capsule_along_zaxis() {
+        let collider = parse_geom(r#"<geom type="capsule" size="0.1 1" zaxis="0 1 0"/>"#).unwrap();
+
+        // ncollide capsules are aligned with their local Y axis
+        let axis = collider.get_rotation() * Vector3::y();
+        assert_relative_eq!(axis, Vector3::y(), epsilon = 1.0e-10);
+    }
+
+    #[test]
+    fn parse_comma_separated_sizes() {
+        let collider = parse_geom(r#"<geom type="capsule" size="0.1,0.2"/>"#).unwrap();
+        let capsule = collider.get_shape().as_shape::<Capsule<f64>>().unwrap();
+        assert_eq!(capsule.radius(), 0.1);
+        assert_eq!(capsule.half_height(), 0.2);
+
+        let collider =
+            parse_geom(r#"<geom type="capsule" size="0.1," fromto="0 0 0 0 0 1"/>"#).unwrap();
+        let capsule = collider.get_shape().as_shape::<Capsule<f64>>().unwrap();
+        assert_eq!(capsule.radius(), 0.1);
+
+        let collider = parse_geom(r#"<geom size="0.3,0,0"/>"#).unwrap();
+        assert_eq!(
+            collider
+                .get_shape()
+                .as_shape::<Ball<f64>>()
+                .unwrap()
+                .radius(),
+            0.3
+        );
+
+        let strict = ParseOptions::builder().strict(true).build();
+        assert_eq!(
+            parse_geom_with_options(r#"<geom type="capsule" size="0.1,0.2"/>"#, &strict)
+                .unwrap_err(),
+            GeomError::InvalidRealVector {
+                attribute: "size",
+                error: ParseRealVectorError::CommaSeparated,
+            }
+        );
+    }
+
+    #[test]
+    fn reject_capsule_radius_without_fromto() {
+        let error = parse_geom(r#"<geom type="capsule" size="0.1"/>"#).unwrap_err();
+        assert_eq!(error, GeomError::CapsuleNeedsFromToOrHalfLength);
+        assert_eq!(
+            error.to_string(),
+            "capsule size only specifies the radius. Either add the half length as the second \
+             size element or specify fromto"
+        );
+    }
+
+    #[test]
+    fn reject_two_component_pos() {
+        let error = parse_geom(r#"<geom type="sphere" size="1" pos="1 2"/>"#).unwrap_err();
+        assert_eq!(
+            error,
+            GeomError::WrongSizeArity {
+                attribute: "pos",
+                expected: 3,
+                actual: 2
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "geom pos must have 3 components, but found 2"
+        );
+    }
+
+    #[test]
+    fn infer_mesh_type_from_mesh_attribute() {
+        // TODO(dschwab): Check the built shape once mesh geoms are supported
+        assert_eq!(
+            parse_geom(r#"<geom mesh="part"/>"#).unwrap_err(),
+            GeomError::UnsupportedType(String::from("mesh"))
+        );
+        assert!(parse_geom(r#"<geom type="sphere" size="1" mesh="part"/>"#).is_ok());
+    }
+
+    #[test]
+    fn geom_with_child_element() {
+        let text = r#"<geom type="sphere" size="1"><site/></geom>"#;
+        assert!(parse_geom(text).is_ok());
+
+        let strict = ParseOptions::builder().strict(true).build();
+        let error = parse_geom_with_options(text, &strict).unwrap_err();
+        assert_eq!(
+            error,
+            GeomError::UnexpectedChildElement(String::from("site"))
+        );
+    }
+
+    #[test]
+    fn match_case_insensitively_when_lenient() {
+        let text = r#"<geom Type="Sphere" size="0.5"/>"#;
+        let collider = parse_geom(text).unwrap();
+        assert_eq!(
+            collider
+                .get_shape()
+                .as_shape::<Ball<f64>>()
+                .unwrap()
+                .radius(),
+            0.5
+        );
+
+        let collider = parse_geom(r#"<geom Type="BOX" Size="0.1 0.2 0.3"/>"#).unwrap();
+        assert_eq!(
+            collider
+                .get_shape()
+                .as_shape::<Cuboid<f64>>()
+                .unwrap()
+                .half_extents(),
+            &Vector3::new(0.1, 0.2, 0.3)
+        );
+
+        let strict = ParseOptions::builder().strict(true).build();
+        assert_eq!(
+            parse_geom_with_options(text, &strict).unwrap_err(),
+            GeomError::MisCasedAttribute(String::from("Type"))
+        );
+        assert_eq!(
+            parse_geom_with_options(r#"<geom type="Sphere" size="0.5"/>"#, &strict).unwrap_err(),
+            GeomError::UnknownType(String::from("Sphere"))
+        );
+    }
+
+    #[test]
+    fn reject_miscased_conflicts_with_fromto() {
+        assert_eq!(
+            parse_geom(r#"<geom type="capsule" size="0.1" Pos="0 0 0" fromto="0 0 0 0 0 2"/>"#)
+                .unwrap_err(),
+            GeomError::FromToWithPos
+        );
+        assert_eq!(
+            parse_geom(r#"<geom type="capsule" size="0.1" Euler="0 90 0" fromto="0 0 0 0 0 2"/>"#)
+                .unwrap_err(),
+            GeomError::OrientationWithFromTo("euler")
+        );
+    }
+
+    #[test]
+    fn reject_non_positive_box_size() {
+        let error = parse_geom(r#"<geom type="box" size="1 0 1"/>"#).unwrap_err();
+        assert_eq!(
+            error,
+            GeomError::NonPositiveSize {
+                component: "y half-extent"
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "geom size y half-extent must be positive"
+        );
+    }
+
+    #[test]
+    fn scale_geom_lengths() {
+        let options = ParseOptions::builder().length_scale(0.001).build();
+        let collider = parse_geom_with_options(
+            r#"<geom type="box" size="100 200 300" pos="1000 0 0"/>"#,
+            &options,
+        )
+        .unwrap();
+
+        let cuboid = collider.get_shape().as_shape::<Cuboid<f64>>().unwrap();
+        assert_relative_eq!(*cuboid.half_extents(), Vector3::new(0.1, 0.2, 0.3));
+        assert_relative_eq!(*collider.get_translation(), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reject_fromto_with_pos() {
+        let error =
+            parse_geom(r#"<geom type="capsule" size="0.1" pos="0 0 0" fromto="0 0 0 0 0 2"/>"#)
+                .unwrap_err();
+        assert_eq!(error, GeomError::FromToWithPos);
+    }
+
+    #[test]
+    fn reject_fromto_with_orientation() {
+        let error =
+            parse_geom(r#"<geom type="capsule" size="0.1" euler="0 90 0" fromto="0 0 0 0 0 2"/>"#)
+                .unwrap_err();
+        assert_eq!(error, GeomError::OrientationWithFromTo("euler"));
+        assert_eq!(
+            error.to_string(),
+            "geom cannot specify both fromto and euler"
+        );
+    }
+
+    #[test]
+    fn classify_collidable_geoms() {
+        let collidable = |text| {
+            let collider = parse_geom(text).unwrap();
+            GeomUserData::from_collider_desc(&collider)
+                .unwrap()
+                .is_collidable()
+        };
+        assert!(collidable(r#"<geom size="1"/>"#));
+        assert!(collidable(r#"<geom size="1" contype="0"/>"#));
+        assert!(!collidable(
+            r#"<geom size="1" contype="0" conaffinity="0"/>"#
+        ));
+    }
+
+    #[test]
+    fn reject_bad_fromto() {
+        let error =
+            parse_geom(r#"<geom type="capsule" size="0.1" fromto="0 0 0 0 2"/>"#).unwrap_err();
+        assert_eq!(error, GeomError::BadFromTo(String::from("0 0 0 0 2")));
+
+        let error =
+            parse_geom(r#"<geom type="capsule" size="0.1" fromto="0 0 0 0 0 inf"/>"#).unwrap_err();
+        assert_eq!(error, GeomError::BadFromTo(String::from("0 0 0 0 0 inf")));
+    }
+
+    #[test]
+    fn parse_geom_user_data() {
+        let collider = parse_geom(
+            r#"<geom size="1" contype="0" conaffinity="2" condim="1" group="3"
+                     friction="0.5" rgba="1 0 0 1"/>"#,
+        )
+        .unwrap();
+
+        let user_data = GeomUserData::from_collider_desc(&collider).unwrap();
+        assert_eq!(user_data.contype, 0);
+        assert_eq!(user_data.conaffinity, 2);
+        assert_eq!(user_data.condim, 1);
+        assert_eq!(user_data.group, 3);
+        assert_eq!(user_data.friction, Vector3::new(0.5, 0.005, 0.0001));
+        assert_eq!(user_data.rgba, Point4::new(1.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn parse_solimp() {
+        let solimp = |text| {
+            let collider = parse_geom(text).unwrap();
+            GeomUserData::from_collider_desc(&collider).unwrap().solimp
+        };
+        assert_eq!(
+            solimp(r#"<geom size="1" solimp="0.8 0.9 0.01"/>"#).as_slice(),
+            &[0.8, 0.9, 0.01, 0.5, 2.0]
+        );
+        assert_eq!(
+            solimp(r#"<geom size="1" solimp="0.8 0.9 0.01 0.4 3"/>"#).as_slice(),
+            &[0.8, 0.9, 0.01, 0.4, 3.0]
+        );
+
+        let error = parse_geom(r#"<geom size="1" solimp="0.8 0.9 0.01 0.4"/>"#).unwrap_err();
+        assert_eq!(
+            error,
+            GeomError::InvalidRealVector {
+                attribute: "solimp",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 5,
+                    actual: 4
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn validate_gap_against_margin() {
+        assert_eq!(
+            parse_geom(r#"<geom size="1" margin="0.01" gap="0.02"/>"#).unwrap_err(),
+            GeomError::GapExceedsMargin
+        );
+
+        let collider = parse_geom(r#"<geom size="1" margin="0.02" gap="0.01"/>"#).unwrap();
+        let user_data = GeomUserData::from_collider_desc(&collider).unwrap();
+        assert_eq!(user_data.margin, 0.02);
+        assert_eq!(user_data.gap, 0.01);
+    }
+
+    #[test]
+    fn parse_padded_sphere_size() {
+        let text = r#"<geom type="sphere" size="0.1 0 0"/>"#;
+        let collider = parse_geom(text).unwrap();
+        let ball = collider.get_shape().as_shape::<Ball<f64>>().unwrap();
+        assert_eq!(ball.radius(), 0.1);
+
+        let strict = ParseOptions::builder().strict(true).build();
+        assert_eq!(
+            parse_geom_with_options(text, &strict).unwrap_err(),
+            GeomError::WrongSizeArity {
+                attribute: "size",
+                expected: 1,
+                actual: 3
+            }
+        );
+    }
+
+    #[test]
+    fn parse_ccd() {
+        let collider = parse_geom(r#"<geom size="0.01" ccd="true"/>"#).unwrap();
+        assert!(GeomUserData::from_collider_desc(&collider).unwrap().ccd);
+
+        let collider = parse_geom(r#"<geom size="0.01"/>"#).unwrap();
+        assert!(!GeomUserData::from_collider_desc(&collider).unwrap().ccd);
+
+        match parse_geom(r#"<geom size="0.01" ccd="yes"/>"#).unwrap_err() {
+            GeomError::InvalidBool { attribute, .. } => assert_eq!(attribute, "ccd"),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn parse_rgb_and_rgba_colors() {
+        let rgba = |text| {
+            GeomUserData::from_collider_desc(&parse_geom(text).unwrap())
+                .unwrap()
+                .rgba
+        };
+        assert_eq!(
+            rgba(r#"<geom size="1" rgba="0.5 0.5 0.5"/>"#),
+            Point4::new(0.5, 0.5, 0.5, 1.0)
+        );
+        assert_eq!(
+            rgba(r#"<geom size="1" rgba="0.5 0.5 0.5 0.2"/>"#),
+            Point4::new(0.5, 0.5, 0.5, 0.2)
+        );
+
+        assert_eq!(
+            parse_geom(r#"<geom size="1" rgba="0.5 0.5"/>"#).unwrap_err(),
+            GeomError::InvalidRealVector {
+                attribute: "rgba",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 4,
+                    actual: 2
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn parse_user_numbers() {
+        let collider = parse_geom(r#"<geom size="1" user="1 2 3"/>"#).unwrap();
+        assert_eq!(
+            GeomUserData::from_collider_desc(&collider).unwrap().user,
+            vec![1.0, 2.0, 3.0]
+        );
+
+        match parse_geom(r#"<geom size="1" user="1 two"/>"#).unwrap_err() {
+            GeomError::InvalidRealVector { attribute, .. } => assert_eq!(attribute, "user"),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn mark_translucent_geoms_transparent() {
+        let collider = parse_geom(r#"<geom size="1" rgba="1 1 1 0.3"/>"#).unwrap();
+        assert!(
+            GeomUserData::from_collider_desc(&collider)
+                .unwrap()
+                .transparent
+        );
+
+        let collider = parse_geom(r#"<geom size="1" rgba="1 1 1 1"/>"#).unwrap();
+        assert!(
+            !GeomUserData::from_collider_desc(&collider)
+                .unwrap()
+                .transparent
+        );
+    }
+
+    #[test]
+    fn take_capsule_fromto_radius_from_defaults() {
+        let doc =
+            roxmltree::Document::parse(r#"<geom type="capsule" fromto="0 0 0 0 0 1"/>"#).unwrap();
+        let mut defaults = HashMap::new();
+        defaults.insert(String::from("size"), String::from("0.05 0.3"));
+        let collider = parse_geom_node_with_defaults::<f64>(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+            defaults,
+            &HashMap::new(),
+        )
+        .unwrap();
+
+        let capsule = collider.get_shape().as_shape::<Capsule<f64>>().unwrap();
+        assert_eq!(capsule.radius(), 0.05);
+        assert_relative_eq!(capsule.half_height(), 0.5);
+    }
+
+    #[test]
+    fn parse_rotated_finite_plane() {
+        let collider =
+            parse_geom(r#"<geom type="plane" size="2 1 0.1" pos="0 0 1" axisangle="1 0 0 90"/>"#)
+                .unwrap();
+
+        let normal = collider.get_rotation() * Vector3::z();
+        assert_relative_eq!(normal, Vector3::new(0.0, -1.0, 0.0), epsilon = 1e-10);
+
+        let quad = GeomUserData::from_collider_desc(&collider)
+            .unwrap()
+            .render_quad
+            .unwrap();
+        assert_relative_eq!(quad[0], Point3::new(-2.0, 0.0, 0.0), epsilon = 1e-10);
+        assert_relative_eq!(quad[2], Point3::new(2.0, 0.0, 2.0), epsilon = 1e-10);
+
+        let collider = parse_geom(r#"<geom type="plane" size="0 0 1"/>"#).unwrap();
+        assert!(GeomUserData::from_collider_desc(&collider)
+            .unwrap()
+            .render_quad
+            .is_none());
+    }
+
+    #[test]
+    fn reject_cylinder_half_length_with_fromto() {
+        assert_eq!(
+            parse_geom(r#"<geom type="cylinder" size="0.1 0.5" fromto="0 0 0 0 0 1"/>"#)
+                .unwrap_err(),
+            GeomError::HalfLengthWithFromTo
+        );
+        let collider =
+            parse_geom(r#"<geom type="cylinder" size="0.1" fromto="0 0 0 0 0 1"/>"#).unwrap();
+        let cylinder = collider.get_shape().as_shape::<Cylinder<f64>>().unwrap();
+        assert_relative_eq!(cylinder.radius(), 0.1);
+        assert_relative_eq!(cylinder.half_height(), 0.5);
+    }
+
+    #[test]
+    fn parse_ellipsoid_geom() {
+        let collider = parse_geom(r#"<geom type="ellipsoid" size="1 2 3" pos="0 0 1"/>"#).unwrap();
+
+        let aabb = collider.get_shape().aabb(&Isometry3::identity());
+        assert_relative_eq!(*aabb.mins(), Point3::new(-1.0, -2.0, -3.0), epsilon = 1e-10);
+        assert_relative_eq!(*aabb.maxs(), Point3::new(1.0, 2.0, 3.0), epsilon = 1e-10);
+        assert_eq!(collider.get_translation(), &Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            ShapeKind::of_collider(&collider),
+            Some(ShapeKind::Ellipsoid)
+        );
+        assert_eq!(
+            ellipsoid_semi_axes(&collider),
+            Some(Vector3::new(1.0, 2.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn reject_ellipsoid_without_three_semi_axes() {
+        assert_eq!(
+            parse_geom(r#"<geom type="ellipsoid" size="1 2"/>"#).unwrap_err(),
+            GeomError::RequiredAttributeMissing("size")
+        );
+        assert_eq!(
+            parse_geom(r#"<geom type="ellipsoid"/>"#).unwrap_err(),
+            GeomError::RequiredAttributeMissing("size")
+        );
+        assert_eq!(
+            parse_geom(r#"<geom type="ellipsoid" size="1 0 1"/>"#).unwrap_err(),
+            GeomError::NonPositiveSize {
+                component: "y semi-axis"
+            }
+        );
+    }
+
+    #[test]
+    fn build_colliders_in_code() {
+        let ball = make_ball(0.5);
+        assert_eq!(
+            ball.get_shape().as_shape::<Ball<f64>>().unwrap().radius(),
+            0.5
+        );
+        assert_eq!(ball.get_density(), 1000.0);
+        assert_eq!(
+            GeomUserData::from_collider_desc(&ball),
+            Some(&GeomUserData::default())
+        );
+
+        let crate_box = make_box(Vector3::new(0.1, 0.2, 0.3));
+        assert_eq!(
+            crate_box
+                .get_shape()
+                .as_shape::<Cuboid<f64>>()
+                .unwrap()
+                .half_extents(),
+            &Vector3::new(0.1, 0.2, 0.3)
+        );
+
+        let capsule = make_capsule(1.0, 0.1);
+        let shape = capsule.get_shape().as_shape::<Capsule<f64>>().unwrap();
+        assert_eq!((shape.half_height(), shape.radius()), (1.0, 0.1));
+        assert_relative_eq!(
+            capsule.get_position().rotation * Vector3::y(),
+            Vector3::z(),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn reject_fromto_shorter_than_epsilon() {
+        let options = ParseOptions::builder().epsilon(0.01).build();
+        let short = r#"<geom type="capsule" size="0.1" fromto="0 0 0 0 0 0.005"/>"#;
+        let long = r#"<geom type="capsule" size="0.1" fromto="0 0 0 0 0 0.02"/>"#;
+
+        assert_eq!(
+            parse_geom_with_options(short, &options).unwrap_err(),
+            GeomError::DegenerateFromTo
+        );
+        assert!(parse_geom_with_options(long, &options).is_ok());
+        assert!(parse_geom(short).is_ok());
+    }
+}
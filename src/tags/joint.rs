@@ -0,0 +1,240 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{
+    parse_real_vector_attribute_with_options, Attr, AttrError, ParseBoolAttributeError,
+    ParseRealVectorError,
+};
+use crate::parse_options::ParseOptions;
+use crate::tags::compiler::CompilerOptions;
+use na::{Point3, Real, Unit, Vector3, U2, U3};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+
+pub(crate) const SUPPORTED_ATTRIBUTES: &[&str] =
+    &["name", "type", "pos", "axis", "limited", "range"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JointType {
+    Hinge,
+    Slide,
+    Ball,
+    Free,
+}
+
+impl Default for JointType {
+    fn default() -> Self {
+        JointType::Hinge
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JointDesc<N: Real> {
+    pub joint_type: JointType,
+    /// Anchor of the joint in the frame of the body containing it.
+    pub pos: Point3<N>,
+    /// Rotation axis of hinges and translation axis of slides in the
+    /// frame of the body containing the joint.
+    pub axis: Unit<Vector3<N>>,
+    /// Lower and upper limit of the joint, or `None` if it is not
+    /// limited. Angles are in radians.
+    pub range: Option<(N, N)>,
+}
+
+impl<N: Real> Default for JointDesc<N> {
+    fn default() -> Self {
+        JointDesc {
+            joint_type: JointType::default(),
+            pos: Point3::origin(),
+            axis: Vector3::z_axis(),
+            range: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JointError {
+    UnknownType(String),
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidBool {
+        attribute: &'static str,
+        error: ParseBoolAttributeError,
+    },
+    ZeroLengthAxis,
+    LimitedWithoutRange,
+}
+
+impl fmt::Display for JointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JointError::UnknownType(joint_type) => write!(f, "unknown joint type '{}'", joint_type),
+            JointError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid joint {}: {}", attribute, error)
+            }
+            JointError::InvalidBool { attribute, error } => {
+                write!(f, "invalid joint {}: {}", attribute, error)
+            }
+            JointError::ZeroLengthAxis => write!(f, "joint axis has zero length"),
+            JointError::LimitedWithoutRange => write!(f, "limited joint must have a range"),
+        }
+    }
+}
+
+impl Error for JointError {}
+
+impl From<AttrError> for JointError {
+    fn from(error: AttrError) -> Self {
+        match error {
+            AttrError::InvalidBool { attribute, error } => {
+                JointError::InvalidBool { attribute, error }
+            }
+            // Joints have no other attributes parsed by `Attr` yet
+            error => unreachable!("unexpected joint attribute error {:?}", error),
+        }
+    }
+}
+
+/// Parse a `joint` element.
+///
+/// A joint with a `range` is limited unless `limited` is false, like
+/// MuJoCo's `limited="auto"`.
+pub fn parse_joint_node<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &roxmltree::Node,
+) -> Result<JointDesc<N>, JointError> {
+    warn_unsupported_attributes(logger, node, SUPPORTED_ATTRIBUTES);
+
+    let mut joint = JointDesc::default();
+
+    if let Some(joint_type) = node.attribute("type") {
+        joint.joint_type = match joint_type {
+            "hinge" => JointType::Hinge,
+            "slide" => JointType::Slide,
+            "ball" => JointType::Ball,
+            "free" => JointType::Free,
+            _ => return Err(JointError::UnknownType(joint_type.to_string())),
+        };
+    }
+
+    if let Some(pos) = node.attribute("pos") {
+        let pos =
+            parse_real_vector_attribute_with_options::<N, U3>(options, pos).map_err(|error| {
+                JointError::InvalidRealVector {
+                    attribute: "pos",
+                    error,
+                }
+            })?;
+        joint.pos = Point3::from(pos * na::convert::<f64, N>(options.length_scale));
+    }
+
+    if let Some(axis) = node.attribute("axis") {
+        let axis =
+            parse_real_vector_attribute_with_options::<N, U3>(options, axis).map_err(|error| {
+                JointError::InvalidRealVector {
+                    attribute: "axis",
+                    error,
+                }
+            })?;
+        joint.axis =
+            Unit::try_new(axis, options.degenerate_epsilon()).ok_or(JointError::ZeroLengthAxis)?;
+    }
+
+    let range = match node.attribute("range") {
+        Some(range) => Some(
+            parse_real_vector_attribute_with_options::<N, U2>(options, range).map_err(|error| {
+                JointError::InvalidRealVector {
+                    attribute: "range",
+                    error,
+                }
+            })?,
+        ),
+        None => None,
+    };
+    joint.range = match (Attr::bool(node, "limited")?, range) {
+        (Some(false), _) | (None, None) => None,
+        (Some(true), None) => return Err(JointError::LimitedWithoutRange),
+        (_, Some(range)) => Some(match joint.joint_type {
+            JointType::Slide => {
+                let scale = na::convert::<f64, N>(options.length_scale);
+                (range[0] * scale, range[1] * scale)
+            }
+            _ => (
+                compiler.angle_to_radians(range[0]),
+                compiler.angle_to_radians(range[1]),
+            ),
+        }),
+    };
+
+    Ok(joint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use std::f64;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    fn parse_joint(text: &str) -> Result<JointDesc<f64>, JointError> {
+        let doc = roxmltree::Document::parse(text).unwrap();
+        parse_joint_node(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+    }
+
+    #[test]
+    fn parse_hinge_joint() {
+        let joint = parse_joint(r#"<joint type="hinge" pos="0 0 0.5" axis="0 2 0"/>"#).unwrap();
+        assert_eq!(joint.joint_type, JointType::Hinge);
+        assert_eq!(joint.pos, Point3::new(0.0, 0.0, 0.5));
+        assert_eq!(joint.axis, Vector3::y_axis());
+    }
+
+    #[test]
+    fn limit_joints_with_range() {
+        let joint = parse_joint(r#"<joint range="-90 90"/>"#).unwrap();
+        let (lower, upper) = joint.range.unwrap();
+        assert_relative_eq!(lower, -f64::consts::FRAC_PI_2);
+        assert_relative_eq!(upper, f64::consts::FRAC_PI_2);
+
+        let joint = parse_joint(r#"<joint type="slide" limited="true" range="0 0.5"/>"#).unwrap();
+        assert_eq!(joint.range, Some((0.0, 0.5)));
+
+        assert_eq!(parse_joint(r#"<joint/>"#).unwrap().range, None);
+    }
+
+    #[test]
+    fn override_limits_with_limited() {
+        let joint = parse_joint(r#"<joint limited="false" range="-90 90"/>"#).unwrap();
+        assert_eq!(joint.range, None);
+
+        assert_eq!(
+            parse_joint(r#"<joint limited="true"/>"#).unwrap_err(),
+            JointError::LimitedWithoutRange
+        );
+    }
+
+    #[test]
+    fn reject_invalid_joints() {
+        assert_eq!(
+            parse_joint(r#"<joint type="weld"/>"#).unwrap_err(),
+            JointError::UnknownType(String::from("weld"))
+        );
+        assert_eq!(
+            parse_joint(r#"<joint axis="0 0 0"/>"#).unwrap_err(),
+            JointError::ZeroLengthAxis
+        );
+    }
+}
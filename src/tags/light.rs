@@ -0,0 +1,137 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{Attr, AttrError, ParseBoolAttributeError, ParseRealVectorError};
+use crate::parse_options::ParseOptions;
+use na::{Point3, Real, Unit, Vector3};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+
+pub(crate) const SUPPORTED_ATTRIBUTES: &[&str] = &["name", "pos", "dir", "directional"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightDesc<N: Real> {
+    /// Position relative to the frame of the containing body.
+    pub position: Point3<N>,
+    /// Direction the light points in, relative to the frame of the
+    /// containing body.
+    pub direction: Unit<Vector3<N>>,
+    /// Directional lights only use `direction` and are infinitely far
+    /// away.
+    pub directional: bool,
+}
+
+impl<N: Real> Default for LightDesc<N> {
+    fn default() -> Self {
+        LightDesc {
+            position: Point3::origin(),
+            direction: Unit::new_unchecked(-Vector3::z()),
+            directional: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightError {
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidBool {
+        attribute: &'static str,
+        error: ParseBoolAttributeError,
+    },
+    ZeroLengthDirection,
+}
+
+impl fmt::Display for LightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LightError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid light {}: {}", attribute, error)
+            }
+            LightError::InvalidBool { attribute, error } => {
+                write!(f, "invalid light {}: {}", attribute, error)
+            }
+            LightError::ZeroLengthDirection => write!(f, "light dir has zero length"),
+        }
+    }
+}
+
+impl Error for LightError {}
+
+impl From<AttrError> for LightError {
+    fn from(error: AttrError) -> Self {
+        match error {
+            AttrError::InvalidRealVector { attribute, error } => {
+                LightError::InvalidRealVector { attribute, error }
+            }
+            AttrError::InvalidBool { attribute, error } => {
+                LightError::InvalidBool { attribute, error }
+            }
+            // Lights have no other typed attributes yet
+            error => unreachable!("unexpected light attribute error {:?}", error),
+        }
+    }
+}
+
+pub fn parse_light_node<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    node: &roxmltree::Node,
+) -> Result<LightDesc<N>, LightError> {
+    warn_unsupported_attributes(logger, node, SUPPORTED_ATTRIBUTES);
+
+    let scale: N = na::convert(options.length_scale);
+    let mut light = LightDesc::default();
+
+    if let Some(pos) = Attr::vec3(options, node, "pos")? {
+        light.position = Point3::from(pos * scale);
+    }
+    if let Some(dir) = Attr::vec3::<N>(options, node, "dir")? {
+        light.direction = Unit::try_new(dir, options.degenerate_epsilon())
+            .ok_or(LightError::ZeroLengthDirection)?;
+    }
+    if let Some(directional) = Attr::bool(node, "directional")? {
+        light.directional = directional;
+    }
+
+    Ok(light)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    fn parse_light(text: &str) -> Result<LightDesc<f64>, LightError> {
+        let doc = roxmltree::Document::parse(text).unwrap();
+        parse_light_node(&logger(), &ParseOptions::default(), &doc.root_element())
+    }
+
+    #[test]
+    fn parse_light_attributes() {
+        let light = parse_light(r#"<light pos="0 0 3" dir="0 1 -1" directional="true"/>"#).unwrap();
+        assert_eq!(light.position, Point3::new(0.0, 0.0, 3.0));
+        assert_relative_eq!(
+            light.direction.into_inner(),
+            Vector3::new(0.0, 1.0, -1.0).normalize()
+        );
+        assert!(light.directional);
+
+        assert_eq!(parse_light("<light/>").unwrap(), LightDesc::default());
+    }
+
+    #[test]
+    fn reject_zero_direction() {
+        assert_eq!(
+            parse_light(r#"<light dir="0 0 0"/>"#).unwrap_err(),
+            LightError::ZeroLengthDirection
+        );
+    }
+}
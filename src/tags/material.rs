@@ -0,0 +1,91 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{parse_real_vector_attribute, ParseRealVectorError};
+use na::{Point4, Real, U4};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+
+/// Visual properties of a material asset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialDesc<N: Real> {
+    pub rgba: Point4<N>,
+}
+
+impl<N: Real> Default for MaterialDesc<N> {
+    fn default() -> Self {
+        MaterialDesc {
+            rgba: Point4::new(N::one(), N::one(), N::one(), N::one()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialError {
+    MissingName,
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+}
+
+impl fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaterialError::MissingName => write!(f, "material must have a name"),
+            MaterialError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid material {}: {}", attribute, error)
+            }
+        }
+    }
+}
+
+impl Error for MaterialError {}
+
+/// Parse a `material` element of the `asset` section.
+///
+/// Materials are only referenced by name, so unnamed materials are
+/// rejected.
+pub fn parse_material_node<N: Real>(
+    logger: &slog::Logger,
+    node: &roxmltree::Node,
+) -> Result<(String, MaterialDesc<N>), MaterialError> {
+    warn_unsupported_attributes(logger, node, &["name", "rgba"]);
+
+    let name = node.attribute("name").ok_or(MaterialError::MissingName)?;
+
+    let mut material = MaterialDesc::default();
+    if let Some(rgba) = node.attribute("rgba") {
+        let rgba = parse_real_vector_attribute::<N, U4>(rgba).map_err(|error| {
+            MaterialError::InvalidRealVector {
+                attribute: "rgba",
+                error,
+            }
+        })?;
+        material.rgba = Point4::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+    }
+
+    Ok((name.to_string(), material))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    #[test]
+    fn parse_material_rgba() {
+        let doc = roxmltree::Document::parse(r#"<material name="red" rgba="1 0 0 1"/>"#).unwrap();
+        let (name, material) = parse_material_node::<f64>(&logger(), &doc.root_element()).unwrap();
+        assert_eq!(name, "red");
+        assert_eq!(material.rgba, Point4::new(1.0, 0.0, 0.0, 1.0));
+
+        let doc = roxmltree::Document::parse(r#"<material rgba="1 0 0 1"/>"#).unwrap();
+        let error = parse_material_node::<f64>(&logger(), &doc.root_element()).unwrap_err();
+        assert_eq!(error, MaterialError::MissingName);
+    }
+}
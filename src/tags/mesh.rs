@@ -0,0 +1,276 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{parse_real_vector_attribute_with_options, ParseRealVectorError};
+use crate::parse_options::ParseOptions;
+use na::{Point3, Quaternion, Real, UnitQuaternion, Vector3, U3, U4};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+use std::num::{ParseFloatError, ParseIntError};
+
+/// A mesh asset with its vertex data given inline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshAsset<N: Real> {
+    /// Vertices in the frame of the mesh, i.e. with `refpos` and
+    /// `refquat` already applied.
+    pub vertices: Vec<Point3<N>>,
+    /// Vertex indices of each triangle. Empty if MuJoCo is left to
+    /// compute the convex hull of the vertices.
+    pub faces: Vec<Point3<usize>>,
+}
+
+impl<N: Real> MeshAsset<N> {
+    /// Radius of the smallest sphere around the origin of the mesh frame
+    /// that contains every vertex. MuJoCo centers meshes on their center
+    /// of mass, so this is the radius of the sphere it fits to the mesh.
+    pub fn bounding_radius(&self) -> N {
+        self.vertices
+            .iter()
+            .map(|vertex| vertex.coords.norm())
+            .fold(N::zero(), |radius, distance| radius.max(distance))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    MissingName,
+    /// Only meshes with inline vertices are supported.
+    MissingVertices,
+    /// The number of vertex coordinates or face indices is not a
+    /// multiple of 3.
+    IncompleteTriple(&'static str),
+    FaceIndexOutOfBounds(usize),
+    ZeroNormQuaternion,
+    NonPositiveScale,
+    InvalidReal {
+        attribute: &'static str,
+        error: ParseFloatError,
+    },
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidInteger {
+        attribute: &'static str,
+        error: ParseIntError,
+    },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshError::MissingName => write!(f, "mesh must have a name"),
+            MeshError::MissingVertices => write!(
+                f,
+                "mesh must have inline vertices. Mesh files are not supported yet"
+            ),
+            MeshError::IncompleteTriple(attribute) => write!(
+                f,
+                "number of values in mesh {} must be a multiple of 3",
+                attribute
+            ),
+            MeshError::FaceIndexOutOfBounds(index) => {
+                write!(f, "mesh face references missing vertex {}", index)
+            }
+            MeshError::ZeroNormQuaternion => write!(f, "mesh refquat has zero norm"),
+            MeshError::NonPositiveScale => write!(f, "mesh scale components must be positive"),
+            MeshError::InvalidReal { attribute, error } => {
+                write!(f, "invalid mesh {}: {}", attribute, error)
+            }
+            MeshError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid mesh {}: {}", attribute, error)
+            }
+            MeshError::InvalidInteger { attribute, error } => {
+                write!(f, "invalid mesh {}: {}", attribute, error)
+            }
+        }
+    }
+}
+
+impl Error for MeshError {}
+
+/// Parse a `mesh` element of the `asset` section.
+///
+/// MuJoCo scales every vertex by `scale` along each axis, subtracts
+/// `refpos` and rotates it by the conjugate of `refquat`, which is done
+/// here as well, so the vertices are in the same frame as in MuJoCo.
+pub fn parse_mesh_node<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    node: &roxmltree::Node,
+) -> Result<(String, MeshAsset<N>), MeshError> {
+    warn_unsupported_attributes(
+        logger,
+        node,
+        &["name", "vertex", "face", "scale", "refpos", "refquat"],
+    );
+
+    let name = node.attribute("name").ok_or(MeshError::MissingName)?;
+    let vertex = node.attribute("vertex").ok_or(MeshError::MissingVertices)?;
+
+    let coordinates = vertex
+        .split_whitespace()
+        .map(|value| value.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| MeshError::InvalidReal {
+            attribute: "vertex",
+            error,
+        })?;
+    if coordinates.len() % 3 != 0 {
+        return Err(MeshError::IncompleteTriple("vertex"));
+    }
+
+    let scale = match node.attribute("scale") {
+        Some(scale) => {
+            let scale = parse_real_vector_attribute_with_options::<N, U3>(options, scale).map_err(
+                |error| MeshError::InvalidRealVector {
+                    attribute: "scale",
+                    error,
+                },
+            )?;
+            if scale.iter().any(|&component| component <= N::zero()) {
+                return Err(MeshError::NonPositiveScale);
+            }
+            scale
+        }
+        None => Vector3::repeat(N::one()),
+    };
+    let refpos = match node.attribute("refpos") {
+        Some(refpos) => parse_real_vector_attribute_with_options::<N, U3>(options, refpos)
+            .map_err(|error| MeshError::InvalidRealVector {
+                attribute: "refpos",
+                error,
+            })?,
+        None => Vector3::zeros(),
+    };
+    let refquat = match node.attribute("refquat") {
+        Some(refquat) => {
+            let quat = parse_real_vector_attribute_with_options::<N, U4>(options, refquat)
+                .map_err(|error| MeshError::InvalidRealVector {
+                    attribute: "refquat",
+                    error,
+                })?;
+            let quat = Quaternion::new(quat[0], quat[1], quat[2], quat[3]);
+            if quat.norm() <= options.degenerate_epsilon() {
+                return Err(MeshError::ZeroNormQuaternion);
+            }
+            UnitQuaternion::from_quaternion(quat)
+        }
+        None => UnitQuaternion::identity(),
+    };
+
+    let vertices = coordinates
+        .chunks(3)
+        .map(|vertex| {
+            let vertex = Point3::new(
+                na::convert::<f64, N>(vertex[0]) * scale.x,
+                na::convert::<f64, N>(vertex[1]) * scale.y,
+                na::convert::<f64, N>(vertex[2]) * scale.z,
+            );
+            refquat.inverse() * (vertex - refpos)
+        })
+        .collect::<Vec<_>>();
+
+    let indices = node
+        .attribute("face")
+        .unwrap_or("")
+        .split_whitespace()
+        .map(|value| value.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| MeshError::InvalidInteger {
+            attribute: "face",
+            error,
+        })?;
+    if indices.len() % 3 != 0 {
+        return Err(MeshError::IncompleteTriple("face"));
+    }
+    if let Some(&index) = indices.iter().find(|&&index| index >= vertices.len()) {
+        return Err(MeshError::FaceIndexOutOfBounds(index));
+    }
+    let faces = indices
+        .chunks(3)
+        .map(|face| Point3::new(face[0], face[1], face[2]))
+        .collect();
+
+    Ok((name.to_string(), MeshAsset { vertices, faces }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    fn parse_mesh(text: &str) -> Result<(String, MeshAsset<f64>), MeshError> {
+        let doc = roxmltree::Document::parse(text).unwrap();
+        parse_mesh_node(&logger(), &ParseOptions::default(), &doc.root_element())
+    }
+
+    #[test]
+    fn parse_inline_mesh() {
+        let (name, mesh) = parse_mesh(
+            r#"<mesh name="tetra" vertex="0 0 0  1 0 0  0 1 0  0 0 1"
+                     face="0 2 1  0 1 3  0 3 2  1 2 3"/>"#,
+        )
+        .unwrap();
+        assert_eq!(name, "tetra");
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.vertices[1], Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(mesh.faces[3], Point3::new(1, 2, 3));
+    }
+
+    #[test]
+    fn apply_reference_frame() {
+        // Quarter turn around the Z axis
+        let (_, mesh) = parse_mesh(
+            r#"<mesh name="rotated" vertex="2 0 0  0 0 0  0 0 1"
+                     refpos="1 0 0" refquat="0.7071068 0 0 0.7071068"/>"#,
+        )
+        .unwrap();
+        assert_relative_eq!(
+            mesh.vertices[0],
+            Point3::new(0.0, -1.0, 0.0),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(mesh.vertices[1], Point3::new(0.0, 1.0, 0.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn apply_non_uniform_scale() {
+        let (_, mesh) =
+            parse_mesh(r#"<mesh name="stretched" vertex="0 0 0  1 1 1  2 -3 0.5" scale="1 2 1"/>"#)
+                .unwrap();
+        assert_eq!(mesh.vertices[1], Point3::new(1.0, 2.0, 1.0));
+        assert_eq!(mesh.vertices[2], Point3::new(2.0, -6.0, 0.5));
+
+        assert_eq!(
+            parse_mesh(r#"<mesh name="flat" vertex="0 0 0" scale="1 0 1"/>"#).unwrap_err(),
+            MeshError::NonPositiveScale
+        );
+        match parse_mesh(r#"<mesh name="short" vertex="0 0 0" scale="2 2"/>"#).unwrap_err() {
+            MeshError::InvalidRealVector { attribute, .. } => assert_eq!(attribute, "scale"),
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn reject_malformed_meshes() {
+        assert_eq!(
+            parse_mesh(r#"<mesh name="file" file="part.stl"/>"#).unwrap_err(),
+            MeshError::MissingVertices
+        );
+        assert_eq!(
+            parse_mesh(r#"<mesh name="short" vertex="0 0 0 1"/>"#).unwrap_err(),
+            MeshError::IncompleteTriple("vertex")
+        );
+        assert_eq!(
+            parse_mesh(r#"<mesh name="dangling" vertex="0 0 0  1 0 0  0 1 0" face="0 1 3"/>"#)
+                .unwrap_err(),
+            MeshError::FaceIndexOutOfBounds(3)
+        );
+    }
+}
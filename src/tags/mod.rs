@@ -0,0 +1,95 @@
+use roxmltree;
+use slog;
+
+pub mod body;
+pub mod compiler;
+pub mod default;
+pub mod geom;
+pub mod joint;
+pub mod light;
+pub mod material;
+pub mod mesh;
+pub mod option;
+pub mod site;
+pub mod texture;
+
+/// Text of the comment directly preceding `node`, ignoring whitespace
+/// between them.
+pub(crate) fn preceding_comment(node: &roxmltree::Node) -> Option<String> {
+    let mut sibling = node.prev_sibling();
+    while let Some(node) = sibling {
+        if node.is_comment() {
+            return node.text().map(|text| text.trim().to_string());
+        }
+        let is_whitespace =
+            node.is_text() && node.text().map_or(true, |text| text.trim().is_empty());
+        if !is_whitespace {
+            return None;
+        }
+        sibling = node.prev_sibling();
+    }
+    None
+}
+
+/// Tag names from the root element down to `node`, e.g.
+/// `mujoco/worldbody/body/geom`.
+pub(crate) fn element_path(node: &roxmltree::Node) -> String {
+    let mut tag_names: Vec<_> = node
+        .ancestors()
+        .filter(|ancestor| ancestor.is_element())
+        .map(|ancestor| ancestor.tag_name().name())
+        .collect();
+    tag_names.reverse();
+    tag_names.join("/")
+}
+
+/// Log a warning for every attribute on `node` that the parser ignores.
+pub(crate) fn warn_unsupported_attributes(
+    logger: &slog::Logger,
+    node: &roxmltree::Node,
+    supported_attributes: &[&str],
+) {
+    warn_unused_attributes(logger, node, supported_attributes, false);
+}
+
+/// Log a warning for every attribute on `node` that the parser ignores,
+/// like `warn_unsupported_attributes`. With `ignore_case`, attributes
+/// that `DefaultedNode::ignore_case` matches are used, so they are not
+/// reported.
+pub(crate) fn warn_unused_attributes(
+    logger: &slog::Logger,
+    node: &roxmltree::Node,
+    supported_attributes: &[&str],
+    ignore_case: bool,
+) {
+    for attribute in unused_attributes(node, supported_attributes, ignore_case) {
+        warn!(logger, "Unsupported attribute. Ignoring";
+              "element" => node.tag_name().name(),
+              "attribute" => attribute);
+    }
+}
+
+/// Names of the attributes of `node` that are not in
+/// `supported_attributes`. With `ignore_case`, a name that only differs
+/// in case from a supported attribute is used in its place, unless the
+/// node also has the exact name.
+pub(crate) fn unused_attributes<'a>(
+    node: &'a roxmltree::Node,
+    supported_attributes: &[&str],
+    ignore_case: bool,
+) -> Vec<&'a str> {
+    node.attributes()
+        .iter()
+        .map(|attribute| attribute.name())
+        .filter(|name| {
+            if supported_attributes.contains(name) {
+                return false;
+            }
+            let used_ignoring_case = ignore_case
+                && supported_attributes.iter().any(|supported| {
+                    supported.eq_ignore_ascii_case(name) && !node.has_attribute(*supported)
+                });
+            !used_ignoring_case
+        })
+        .collect()
+}
@@ -0,0 +1,289 @@
+use super::{element_path, warn_unsupported_attributes};
+use crate::attributes::{
+    parse_real_attribute, parse_real_vector_attribute_with_options, ParseRealVectorError,
+};
+use crate::parse_options::ParseOptions;
+use na::{Real, Vector3, U3};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+use std::num::ParseFloatError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionConfig<N: Real> {
+    pub timestep: N,
+    pub gravity: Vector3<N>,
+    /// Integrator requested by the model. nphysics always uses a
+    /// semi-implicit Euler integrator, so it is only recorded.
+    pub integrator: Integrator,
+    flags: OptionFlags,
+}
+
+impl<N: Real> Default for OptionConfig<N> {
+    fn default() -> Self {
+        OptionConfig {
+            timestep: na::convert(0.002),
+            gravity: Vector3::new(N::zero(), N::zero(), na::convert(-9.81)),
+            integrator: Integrator::default(),
+            flags: OptionFlags::default(),
+        }
+    }
+}
+
+impl<N: Real> OptionConfig<N> {
+    pub fn flags(&self) -> &OptionFlags {
+        &self.flags
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integrator {
+    Euler,
+    RK4,
+    Implicit,
+    ImplicitFast,
+}
+
+impl Default for Integrator {
+    fn default() -> Self {
+        Integrator::Euler
+    }
+}
+
+/// Simulation features toggled by the `flag` element. Every feature is
+/// enabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionFlags {
+    pub constraint: bool,
+    pub equality: bool,
+    pub limit: bool,
+    pub contact: bool,
+    pub passive: bool,
+    pub gravity: bool,
+    pub filterparent: bool,
+}
+
+impl Default for OptionFlags {
+    fn default() -> Self {
+        OptionFlags {
+            constraint: true,
+            equality: true,
+            limit: true,
+            contact: true,
+            passive: true,
+            gravity: true,
+            filterparent: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionError {
+    InvalidReal {
+        attribute: &'static str,
+        error: ParseFloatError,
+    },
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    NonPositiveTimestep,
+    UnknownIntegrator(String),
+    InvalidFlag {
+        flag: String,
+        value: String,
+    },
+    /// Path of an element the parser does not know.
+    UnknownElement(String),
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionError::InvalidReal { attribute, error } => {
+                write!(f, "invalid option {}: {}", attribute, error)
+            }
+            OptionError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid option {}: {}", attribute, error)
+            }
+            OptionError::NonPositiveTimestep => write!(f, "option timestep must be positive"),
+            OptionError::UnknownIntegrator(integrator) => write!(
+                f,
+                "unknown integrator '{}'. Expected 'Euler', 'RK4', 'implicit' or 'implicitfast'",
+                integrator
+            ),
+            OptionError::InvalidFlag { flag, value } => write!(
+                f,
+                "option flag {} must be 'enable' or 'disable', but found '{}'",
+                flag, value
+            ),
+            OptionError::UnknownElement(path) => write!(f, "unknown element '{}'", path),
+        }
+    }
+}
+
+impl Error for OptionError {}
+
+pub fn parse_option_node<N: Real>(
+    logger: &slog::Logger,
+    parse_options: &ParseOptions,
+    node: &roxmltree::Node,
+) -> Result<OptionConfig<N>, OptionError> {
+    warn_unsupported_attributes(logger, node, &["timestep", "gravity", "integrator"]);
+
+    let mut options = OptionConfig::default();
+
+    if let Some(timestep) = node.attribute("timestep") {
+        options.timestep =
+            parse_real_attribute(timestep).map_err(|error| OptionError::InvalidReal {
+                attribute: "timestep",
+                error,
+            })?;
+        if options.timestep <= N::zero() {
+            return Err(OptionError::NonPositiveTimestep);
+        }
+    }
+
+    if let Some(gravity) = node.attribute("gravity") {
+        options.gravity = parse_real_vector_attribute_with_options::<N, U3>(parse_options, gravity)
+            .map_err(|error| OptionError::InvalidRealVector {
+                attribute: "gravity",
+                error,
+            })?;
+    }
+
+    if let Some(integrator) = node.attribute("integrator") {
+        options.integrator = match integrator {
+            "Euler" => Integrator::Euler,
+            "RK4" => Integrator::RK4,
+            "implicit" => Integrator::Implicit,
+            "implicitfast" => Integrator::ImplicitFast,
+            _ => return Err(OptionError::UnknownIntegrator(integrator.to_string())),
+        };
+        if options.integrator != Integrator::Euler {
+            warn!(logger, "Integrator is not supported by nphysics. Using semi-implicit Euler";
+                  "integrator" => integrator);
+        }
+    }
+
+    for child in node.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "flag" => options.flags = parse_flag_node(logger, &child)?,
+            _ if parse_options.strict_unknown_elements => {
+                return Err(OptionError::UnknownElement(element_path(&child)));
+            }
+            tag_name => {
+                warn!(logger, "Unsupported option element. Ignoring"; "element" => tag_name)
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_flag_node(
+    logger: &slog::Logger,
+    node: &roxmltree::Node,
+) -> Result<OptionFlags, OptionError> {
+    let mut flags = OptionFlags::default();
+
+    for attribute in node.attributes() {
+        let enabled = match attribute.value() {
+            "enable" => true,
+            "disable" => false,
+            value => {
+                return Err(OptionError::InvalidFlag {
+                    flag: attribute.name().to_string(),
+                    value: value.to_string(),
+                })
+            }
+        };
+        match attribute.name() {
+            "constraint" => flags.constraint = enabled,
+            "equality" => flags.equality = enabled,
+            "limit" => flags.limit = enabled,
+            "contact" => flags.contact = enabled,
+            "passive" => flags.passive = enabled,
+            "gravity" => flags.gravity = enabled,
+            "filterparent" => flags.filterparent = enabled,
+            flag => warn!(logger, "Unsupported option flag. Ignoring"; "flag" => flag),
+        }
+    }
+
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    #[test]
+    fn parse_timestep_and_gravity() {
+        let doc =
+            roxmltree::Document::parse(r#"<option timestep="0.01" gravity="0 0 -1"/>"#).unwrap();
+        let options =
+            parse_option_node::<f64>(&logger(), &ParseOptions::default(), &doc.root_element())
+                .unwrap();
+        assert_eq!(options.timestep, 0.01);
+        assert_eq!(options.gravity, Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn parse_integrator() {
+        let doc = roxmltree::Document::parse(r#"<option integrator="RK4"/>"#).unwrap();
+        let options =
+            parse_option_node::<f64>(&logger(), &ParseOptions::default(), &doc.root_element())
+                .unwrap();
+        assert_eq!(options.integrator, Integrator::RK4);
+
+        let doc = roxmltree::Document::parse(r#"<option integrator="Verlet"/>"#).unwrap();
+        let error =
+            parse_option_node::<f64>(&logger(), &ParseOptions::default(), &doc.root_element())
+                .unwrap_err();
+        assert_eq!(
+            error,
+            OptionError::UnknownIntegrator(String::from("Verlet"))
+        );
+    }
+
+    #[test]
+    fn reject_non_positive_timestep() {
+        let doc = roxmltree::Document::parse(r#"<option timestep="0"/>"#).unwrap();
+        let error =
+            parse_option_node::<f64>(&logger(), &ParseOptions::default(), &doc.root_element())
+                .unwrap_err();
+        assert_eq!(error, OptionError::NonPositiveTimestep);
+    }
+
+    #[test]
+    fn parse_flags() {
+        let doc = roxmltree::Document::parse(
+            r#"<option><flag contact="disable" gravity="enable"/></option>"#,
+        )
+        .unwrap();
+        let options =
+            parse_option_node::<f64>(&logger(), &ParseOptions::default(), &doc.root_element())
+                .unwrap();
+        assert!(!options.flags().contact);
+        assert!(options.flags().gravity);
+        assert!(options.flags().limit);
+
+        let doc = roxmltree::Document::parse(r#"<option><flag contact="off"/></option>"#).unwrap();
+        let error =
+            parse_option_node::<f64>(&logger(), &ParseOptions::default(), &doc.root_element())
+                .unwrap_err();
+        assert_eq!(
+            error,
+            OptionError::InvalidFlag {
+                flag: String::from("contact"),
+                value: String::from("off")
+            }
+        );
+    }
+}
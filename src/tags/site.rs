@@ -0,0 +1,265 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{self, Attr, AttrError, ParseOrientationError, ParseRealVectorError};
+use crate::parse_options::ParseOptions;
+use crate::tags::compiler::CompilerOptions;
+use crate::tags::geom::{self, ShapeKind};
+use na::{Isometry3, Real, Translation3, Vector3};
+use nalgebra as na;
+use ncollide3d::world::CollisionGroups;
+use nphysics3d::object::ColliderDesc;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+
+pub(crate) const SUPPORTED_ATTRIBUTES: &[&str] = &[
+    "name",
+    "type",
+    "size",
+    "pos",
+    "quat",
+    "axisangle",
+    "euler",
+    "xyaxes",
+    "zaxis",
+];
+
+/// A site marks a frame of interest on a body, e.g. for sensors. It
+/// takes no part in collisions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteDesc<N: Real> {
+    /// Position relative to the frame of the containing body.
+    pub position: Isometry3<N>,
+    /// First component of the size, i.e. the radius of sphere and
+    /// capsule sites.
+    pub radius: N,
+    /// Kind of the shape the site is rendered as.
+    pub shape_kind: ShapeKind,
+    /// Size in the same format as the size of a geom of the same type.
+    /// Missing components keep MuJoCo's default of 0.005.
+    pub size: Vector3<N>,
+}
+
+impl<N: Real> Default for SiteDesc<N> {
+    fn default() -> Self {
+        let size = na::convert(0.005);
+        SiteDesc {
+            position: Isometry3::identity(),
+            radius: size,
+            shape_kind: ShapeKind::Ball,
+            size: Vector3::repeat(size),
+        }
+    }
+}
+
+impl<N: Real> SiteDesc<N> {
+    /// Collider of the shape the site is rendered as, e.g. for drawing
+    /// site markers. It is positioned relative to the containing body
+    /// like the site, and its collision groups exclude every other
+    /// group, so it never collides when added to a world.
+    pub fn shape_collider(&self) -> ColliderDesc<N> {
+        let collider = match self.shape_kind {
+            ShapeKind::Box => geom::make_box(self.size),
+            ShapeKind::Capsule => geom::make_capsule(self.size.y, self.size.x),
+            ShapeKind::Cylinder => geom::make_cylinder(self.size.y, self.size.x),
+            ShapeKind::Ellipsoid => geom::make_ellipsoid(self.size),
+            _ => geom::make_ball(self.size.x),
+        };
+        let position = self.position * collider.get_position();
+        collider
+            .position(position)
+            .collision_groups(CollisionGroups::new().with_whitelist(&[]))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SiteError {
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidOrientation(ParseOrientationError),
+    UnsupportedType(String),
+}
+
+impl fmt::Display for SiteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SiteError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid site {}: {}", attribute, error)
+            }
+            SiteError::InvalidOrientation(error) => {
+                write!(f, "invalid site orientation: {}", error)
+            }
+            SiteError::UnsupportedType(site_type) => {
+                write!(f, "unsupported site type '{}'", site_type)
+            }
+        }
+    }
+}
+
+impl Error for SiteError {}
+
+impl From<AttrError> for SiteError {
+    fn from(error: AttrError) -> Self {
+        match error {
+            AttrError::InvalidRealVector { attribute, error } => {
+                SiteError::InvalidRealVector { attribute, error }
+            }
+            // Sites have no other typed attributes yet
+            error => unreachable!("unexpected site attribute error {:?}", error),
+        }
+    }
+}
+
+impl From<ParseOrientationError> for SiteError {
+    fn from(error: ParseOrientationError) -> Self {
+        SiteError::InvalidOrientation(error)
+    }
+}
+
+pub fn parse_site_node<N: Real>(
+    logger: &slog::Logger,
+    options: &ParseOptions,
+    compiler: &CompilerOptions<N>,
+    node: &roxmltree::Node,
+) -> Result<SiteDesc<N>, SiteError> {
+    warn_unsupported_attributes(logger, node, SUPPORTED_ATTRIBUTES);
+
+    let scale: N = na::convert(options.length_scale);
+    let mut site = SiteDesc::default();
+
+    let translation = Attr::vec3(options, node, "pos")?.unwrap_or_else(Vector3::zeros) * scale;
+    let rotation = attributes::parse_orientation_attribute(options, compiler, node)?;
+    site.position = Isometry3::from_parts(
+        Translation3::new(translation.x, translation.y, translation.z),
+        rotation,
+    );
+
+    site.shape_kind = match node.attribute("type").unwrap_or("sphere") {
+        "sphere" => ShapeKind::Ball,
+        "box" => ShapeKind::Box,
+        "capsule" => ShapeKind::Capsule,
+        "cylinder" => ShapeKind::Cylinder,
+        "ellipsoid" => ShapeKind::Ellipsoid,
+        site_type => return Err(SiteError::UnsupportedType(site_type.to_string())),
+    };
+
+    if let Some(size) = node.attribute("size") {
+        let values = size
+            .split_whitespace()
+            .map(attributes::parse_real_attribute)
+            .collect::<Result<Vec<N>, _>>()
+            .map_err(|error| SiteError::InvalidRealVector {
+                attribute: "size",
+                error: error.into(),
+            })?;
+        if values.len() > 3 {
+            return Err(SiteError::InvalidRealVector {
+                attribute: "size",
+                error: ParseRealVectorError::WrongNumberOfElements {
+                    expected: 3,
+                    actual: values.len(),
+                },
+            });
+        }
+        for (component, value) in values.into_iter().enumerate() {
+            site.size[component] = value * scale;
+        }
+        site.radius = site.size.x;
+    }
+
+    Ok(site)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ncollide3d::shape::Cuboid;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    #[test]
+    fn parse_site() {
+        let doc =
+            roxmltree::Document::parse(r#"<site name="tip" pos="0 0 1" size="0.02"/>"#).unwrap();
+        let site = parse_site_node::<f64>(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            site.position.translation.vector,
+            Vector3::new(0.0, 0.0, 1.0)
+        );
+        assert_eq!(site.radius, 0.02);
+        assert_eq!(site.shape_kind, ShapeKind::Ball);
+    }
+
+    #[test]
+    fn parse_box_site() {
+        let doc = roxmltree::Document::parse(
+            r#"<site name="marker" type="box" pos="1 0 0" size="0.1 0.2 0.3"/>"#,
+        )
+        .unwrap();
+        let site = parse_site_node::<f64>(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap();
+
+        assert_eq!(site.shape_kind, ShapeKind::Box);
+        let collider = site.shape_collider();
+        assert_eq!(
+            collider
+                .get_shape()
+                .as_shape::<Cuboid<f64>>()
+                .unwrap()
+                .half_extents(),
+            &Vector3::new(0.1, 0.2, 0.3)
+        );
+        assert_eq!(
+            collider.get_position().translation.vector,
+            Vector3::new(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn parse_ellipsoid_site() {
+        let doc =
+            roxmltree::Document::parse(r#"<site type="ellipsoid" size="0.1 0.2 0.3"/>"#).unwrap();
+        let site = parse_site_node::<f64>(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap();
+
+        assert_eq!(site.shape_kind, ShapeKind::Ellipsoid);
+        assert_eq!(
+            geom::ellipsoid_semi_axes(&site.shape_collider()),
+            Some(Vector3::new(0.1, 0.2, 0.3))
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_site_type() {
+        let doc = roxmltree::Document::parse(r#"<site type="mesh"/>"#).unwrap();
+        let error = parse_site_node::<f64>(
+            &logger(),
+            &ParseOptions::default(),
+            &CompilerOptions::default(),
+            &doc.root_element(),
+        )
+        .unwrap_err();
+        assert_eq!(error, SiteError::UnsupportedType(String::from("mesh")));
+    }
+}
@@ -0,0 +1,179 @@
+use super::warn_unsupported_attributes;
+use crate::attributes::{parse_real_vector_attribute, ParseRealVectorError};
+use na::{Point3, Real, U3};
+use nalgebra as na;
+use roxmltree;
+use slog;
+use std::error::Error;
+use std::fmt;
+use std::num::ParseIntError;
+
+/// Procedural image MuJoCo generates for a texture without a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinTexture {
+    Gradient,
+    Checker,
+    Flat,
+}
+
+/// Source of the image of a texture asset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureAsset<N: Real> {
+    /// A procedural texture, with enough information for renderers to
+    /// regenerate the image.
+    Builtin {
+        kind: BuiltinTexture,
+        rgb1: Point3<N>,
+        rgb2: Point3<N>,
+        width: usize,
+        height: usize,
+    },
+    /// Path of an image file, as written in the document.
+    File(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureError {
+    UnknownBuiltin(String),
+    /// The texture has neither a builtin type nor a file.
+    MissingSource,
+    InvalidRealVector {
+        attribute: &'static str,
+        error: ParseRealVectorError,
+    },
+    InvalidInteger {
+        attribute: &'static str,
+        error: ParseIntError,
+    },
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::UnknownBuiltin(builtin) => {
+                write!(f, "unknown builtin texture '{}'", builtin)
+            }
+            TextureError::MissingSource => write!(f, "texture must have a builtin type or a file"),
+            TextureError::InvalidRealVector { attribute, error } => {
+                write!(f, "invalid texture {}: {}", attribute, error)
+            }
+            TextureError::InvalidInteger { attribute, error } => {
+                write!(f, "invalid texture {}: {}", attribute, error)
+            }
+        }
+    }
+}
+
+impl Error for TextureError {}
+
+/// Parse a `texture` element of the `asset` section.
+///
+/// Skyboxes are usually left unnamed, so the name is optional.
+pub fn parse_texture_node<N: Real>(
+    logger: &slog::Logger,
+    node: &roxmltree::Node,
+) -> Result<(Option<String>, TextureAsset<N>), TextureError> {
+    warn_unsupported_attributes(
+        logger,
+        node,
+        &[
+            "name", "type", "builtin", "rgb1", "rgb2", "width", "height", "file",
+        ],
+    );
+
+    let name = node.attribute("name").map(str::to_string);
+
+    let kind = match node.attribute("builtin").unwrap_or("none") {
+        "none" => {
+            let file = node.attribute("file").ok_or(TextureError::MissingSource)?;
+            return Ok((name, TextureAsset::File(file.to_string())));
+        }
+        "gradient" => BuiltinTexture::Gradient,
+        "checker" => BuiltinTexture::Checker,
+        "flat" => BuiltinTexture::Flat,
+        builtin => return Err(TextureError::UnknownBuiltin(builtin.to_string())),
+    };
+
+    let texture = TextureAsset::Builtin {
+        kind,
+        rgb1: parse_rgb(node, "rgb1")?.unwrap_or_else(|| gray(0.8)),
+        rgb2: parse_rgb(node, "rgb2")?.unwrap_or_else(|| gray(0.5)),
+        width: parse_dimension(node, "width")?,
+        height: parse_dimension(node, "height")?,
+    };
+
+    Ok((name, texture))
+}
+
+fn gray<N: Real>(value: f64) -> Point3<N> {
+    let value = na::convert(value);
+    Point3::new(value, value, value)
+}
+
+fn parse_rgb<N: Real>(
+    node: &roxmltree::Node,
+    attribute: &'static str,
+) -> Result<Option<Point3<N>>, TextureError> {
+    match node.attribute(attribute) {
+        Some(rgb) => parse_real_vector_attribute::<N, U3>(rgb)
+            .map(|rgb| Some(Point3::from(rgb)))
+            .map_err(|error| TextureError::InvalidRealVector { attribute, error }),
+        None => Ok(None),
+    }
+}
+
+fn parse_dimension(node: &roxmltree::Node, attribute: &'static str) -> Result<usize, TextureError> {
+    match node.attribute(attribute) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map_err(|error| TextureError::InvalidInteger { attribute, error }),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logger() -> slog::Logger {
+        slog::Logger::root(slog::Discard, o!())
+    }
+
+    fn parse_texture(text: &str) -> Result<(Option<String>, TextureAsset<f64>), TextureError> {
+        let doc = roxmltree::Document::parse(text).unwrap();
+        parse_texture_node(&logger(), &doc.root_element())
+    }
+
+    #[test]
+    fn parse_checker_texture() {
+        let (name, texture) = parse_texture(
+            r#"<texture name="grid" type="2d" builtin="checker"
+                        rgb1="0.1 0.2 0.3" rgb2="0.2 0.3 0.4" width="512" height="256"/>"#,
+        )
+        .unwrap();
+        assert_eq!(name, Some(String::from("grid")));
+        assert_eq!(
+            texture,
+            TextureAsset::Builtin {
+                kind: BuiltinTexture::Checker,
+                rgb1: Point3::new(0.1, 0.2, 0.3),
+                rgb2: Point3::new(0.2, 0.3, 0.4),
+                width: 512,
+                height: 256,
+            }
+        );
+    }
+
+    #[test]
+    fn reject_textures_without_source() {
+        assert_eq!(
+            parse_texture(r#"<texture name="empty"/>"#).unwrap_err(),
+            TextureError::MissingSource
+        );
+        assert_eq!(
+            parse_texture(r#"<texture builtin="noise"/>"#).unwrap_err(),
+            TextureError::UnknownBuiltin(String::from("noise"))
+        );
+    }
+}
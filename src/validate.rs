@@ -0,0 +1,233 @@
+use crate::attributes::commas_as_whitespace;
+use crate::mjcf_model::{MJCFModel, MJCFParseErrorKind, MJCFParseResult};
+use crate::parse_options::ParseOptions;
+use crate::tags::geom;
+use na::Real;
+use nalgebra as na;
+use roxmltree;
+use std::ops::RangeInclusive;
+
+/// An attribute with the wrong number of components.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DimensionIssue {
+    /// Tag name of the element, e.g. `geom`.
+    pub element: String,
+    /// Name of the element, or the name the parser would give it.
+    pub name: String,
+    pub attribute: &'static str,
+    /// Number of components the attribute may have.
+    pub expected: RangeInclusive<usize>,
+    pub actual: usize,
+}
+
+impl<N: Real> MJCFModel<N> {
+    /// Check the number of components of every geom `size`, `pos` and
+    /// `fromto` and every body `pos` without building any shapes.
+    ///
+    /// This is a quick structural lint. Attributes taken from default
+    /// classes and the values of the components are not checked. Like
+    /// when parsing leniently, components may be separated by commas.
+    pub fn validate_dimensions(text: &str) -> MJCFParseResult<Vec<DimensionIssue>> {
+        let doc = roxmltree::Document::parse(text)?;
+        let root = doc.root_element();
+        if !root.has_tag_name("mujoco") {
+            return Err(
+                MJCFParseErrorKind::NotMuJoCoModel(root.tag_name().name().to_string()).into(),
+            );
+        }
+
+        let mut issues = vec![];
+        let mut num_geoms = 0;
+        let mut num_bodies = 0;
+        for worldbody in root
+            .children()
+            .filter(|child| child.has_tag_name("worldbody"))
+        {
+            // Descendants are visited in document order, which is also
+            // the order unnamed elements are numbered in
+            for node in worldbody.descendants().filter(|node| node.is_element()) {
+                match node.tag_name().name() {
+                    "geom" => {
+                        let name = element_name(&node, "geom", num_geoms);
+                        num_geoms += 1;
+                        check_geom(&node, &name, &mut issues);
+                    }
+                    "body" => {
+                        let name = element_name(&node, "body", num_bodies);
+                        num_bodies += 1;
+                        check_arity(&node, "body", &name, "pos", 3..=3, &mut issues);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(issues)
+    }
+}
+
+fn element_name(node: &roxmltree::Node, prefix: &str, order: usize) -> String {
+    match node.attribute("name") {
+        Some(name) => name.to_string(),
+        None => format!("{}{}", prefix, order),
+    }
+}
+
+fn check_geom(node: &roxmltree::Node, name: &str, issues: &mut Vec<DimensionIssue>) {
+    let has_fromto = node.has_attribute("fromto");
+    // Like in MuJoCo, the size may have up to three components even if
+    // the shape uses fewer. The length of shapes given by fromto is not
+    // part of their size.
+    let expected_size = match (geom::default_geom_type(node), has_fromto) {
+        ("sphere", _) => Some(1..=3),
+        ("capsule", true) | ("cylinder", true) => Some(1..=3),
+        ("capsule", false) | ("cylinder", false) => Some(2..=3),
+        ("box", true) => Some(2..=3),
+        ("box", false) | ("ellipsoid", _) | ("plane", _) => Some(3..=3),
+        _ => None,
+    };
+    if let Some(expected) = expected_size {
+        check_arity(node, "geom", name, "size", expected, issues);
+    }
+    check_arity(node, "geom", name, "pos", 3..=3, issues);
+    check_arity(node, "geom", name, "fromto", 6..=6, issues);
+}
+
+fn check_arity(
+    node: &roxmltree::Node,
+    element: &str,
+    name: &str,
+    attribute: &'static str,
+    expected: RangeInclusive<usize>,
+    issues: &mut Vec<DimensionIssue>,
+) {
+    if let Some(value) = node.attribute(attribute) {
+        // Commas are only rejected in strict mode
+        let value = commas_as_whitespace(&ParseOptions::default(), value)
+            .expect("Commas are accepted by default");
+        let actual = value.split_whitespace().count();
+        if !expected.contains(&actual) {
+            issues.push(DimensionIssue {
+                element: element.to_string(),
+                name: name.to_string(),
+                attribute,
+                expected,
+                actual,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_mis_sized_attributes() {
+        let issues = MJCFModel::<f64>::validate_dimensions(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="crate" type="box" size="1 1"/>
+                   <body pos="0 0 1">
+                     <geom size="0.1" pos="0 1"/>
+                     <geom type="capsule" size="0.1" fromto="0 0 0 0 0 1"/>
+                   </body>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            issues,
+            vec![
+                DimensionIssue {
+                    element: String::from("geom"),
+                    name: String::from("crate"),
+                    attribute: "size",
+                    expected: 3..=3,
+                    actual: 2,
+                },
+                DimensionIssue {
+                    element: String::from("geom"),
+                    name: String::from("geom1"),
+                    attribute: "pos",
+                    expected: 3..=3,
+                    actual: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn accept_sizes_with_unused_components() {
+        let issues = MJCFModel::<f64>::validate_dimensions(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom type="sphere" size="0.1 0 0"/>
+                   <geom type="capsule" size="0.1 0 0" fromto="0 0 0 0 0 1"/>
+                   <geom type="capsule" size="0.1 0.5 0"/>
+                   <geom type="box" size="1 1 0" fromto="0 0 0 0 0 1"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn find_mis_sized_fromto_capsule() {
+        let issues = MJCFModel::<f64>::validate_dimensions(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="rod" type="capsule" size="" fromto="0 0 0 0 0 1"/>
+                   <geom name="ball" size="0.1 0 0 0"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            issues,
+            vec![
+                DimensionIssue {
+                    element: String::from("geom"),
+                    name: String::from("rod"),
+                    attribute: "size",
+                    expected: 1..=3,
+                    actual: 0,
+                },
+                DimensionIssue {
+                    element: String::from("geom"),
+                    name: String::from("ball"),
+                    attribute: "size",
+                    expected: 1..=3,
+                    actual: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn count_comma_separated_components() {
+        let issues = MJCFModel::<f64>::validate_dimensions(
+            r#"<mujoco>
+                 <worldbody>
+                   <geom name="crate" type="box" size="1,1,1" pos="0,0"/>
+                 </worldbody>
+               </mujoco>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            issues,
+            vec![DimensionIssue {
+                element: String::from("geom"),
+                name: String::from("crate"),
+                attribute: "pos",
+                expected: 3..=3,
+                actual: 2,
+            }]
+        );
+    }
+}